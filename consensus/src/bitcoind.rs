@@ -0,0 +1,548 @@
+// Bitcoin protocol consensus library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transaction representation matching JSON produced by Bitcoin Core
+//! `decoderawtransaction` RPC command.
+//!
+//! Output descriptors (`desc`) and addresses (`address`) are not a part of the
+//! consensus layer and are omitted from the script pubkey objects.
+
+use amplify::hex::ToHex;
+use serde::{Serialize, Serializer};
+
+use crate::opcodes::*;
+use crate::{ConsensusEncode, Instruction, Sats, ScriptRef, Tx, TxIn, TxOut, Weight};
+
+/// Serializes transaction in the Bitcoin Core JSON format. Can be used as a
+/// `#[serde(serialize_with = "bc::bitcoind::serialize")]` field attribute.
+pub fn serialize<S: Serializer>(tx: &Tx, serializer: S) -> Result<S::Ok, S::Error> {
+    BitcoindTx::from(tx).serialize(serializer)
+}
+
+/// Transaction in the Bitcoin Core JSON format.
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct BitcoindTx {
+    /// Transaction id.
+    pub txid: String,
+    /// Witness transaction id.
+    pub hash: String,
+    /// Transaction version.
+    pub version: i32,
+    /// Size of the consensus-serialized transaction in bytes.
+    pub size: usize,
+    /// Virtual size of the transaction.
+    pub vsize: u32,
+    /// Weight of the transaction.
+    pub weight: u32,
+    /// Consensus value of the transaction lock time.
+    pub locktime: u32,
+    /// Transaction inputs.
+    pub vin: Vec<BitcoindTxIn>,
+    /// Transaction outputs.
+    pub vout: Vec<BitcoindTxOut>,
+}
+
+/// Transaction input in the Bitcoin Core JSON format.
+///
+/// Coinbase inputs contain only `coinbase`, `txinwitness` and `sequence`
+/// fields; other inputs have all fields except `coinbase`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+pub struct BitcoindTxIn {
+    /// Hex of the coinbase input signature script.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coinbase: Option<String>,
+    /// Id of the transaction containing the spent output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    /// Number of the spent output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vout: Option<u32>,
+    /// Input signature script.
+    #[serde(rename = "scriptSig", skip_serializing_if = "Option::is_none")]
+    pub script_sig: Option<BitcoindScriptSig>,
+    /// Hex of the witness stack elements.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub txinwitness: Vec<String>,
+    /// Consensus value of the input sequence number.
+    pub sequence: u32,
+}
+
+/// Transaction output in the Bitcoin Core JSON format.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+pub struct BitcoindTxOut {
+    /// Output value, serialized in bitcoins (see [`serialize_btc`]).
+    #[serde(serialize_with = "serialize_btc")]
+    pub value: Sats,
+    /// Number of the output in the transaction.
+    pub n: u32,
+    /// Output script pubkey.
+    #[serde(rename = "scriptPubKey")]
+    pub script_pubkey: BitcoindScriptPubkey,
+}
+
+/// Input signature script in the Bitcoin Core JSON format.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+pub struct BitcoindScriptSig {
+    /// Script assembly, with the sighash types of DER signatures decoded.
+    pub asm: String,
+    /// Hex of the script.
+    pub hex: String,
+}
+
+/// Output script pubkey in the Bitcoin Core JSON format.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+pub struct BitcoindScriptPubkey {
+    /// Script assembly.
+    pub asm: String,
+    /// Hex of the script.
+    pub hex: String,
+    /// Script type name, as used by Bitcoin Core (`pubkeyhash`,
+    /// `witness_v1_taproot`, `nulldata` etc).
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+}
+
+/// Serializes an amount in bitcoins as a number with up to 8 decimal digits,
+/// like Bitcoin Core does.
+///
+/// The number is parsed from the exact decimal representation of the amount.
+/// Since serde numbers are `f64`, amounts with more than 15 significant digits
+/// (above 10 million bitcoins with a satoshi precision) may have their last
+/// digit rounded.
+pub fn serialize_btc<S: Serializer>(value: &Sats, serializer: S) -> Result<S::Ok, S::Error> {
+    let btc = format!("{}.{:08}", value.btc_floor(), value.sats_rem());
+    serializer.serialize_f64(btc.parse().expect("decimal number"))
+}
+
+impl From<&Tx> for BitcoindTx {
+    fn from(tx: &Tx) -> Self {
+        let is_coinbase = tx.inputs.len() == 1 && tx.inputs[0].prev_output.txid.is_coinbase();
+        BitcoindTx {
+            txid: tx.txid().to_string(),
+            hash: tx.wtxid().to_string(),
+            version: tx.version.to_consensus_i32(),
+            size: tx.consensus_serialize().len(),
+            vsize: tx.vbytes().to_u32(),
+            weight: tx.weight_units().to_u32(),
+            locktime: tx.lock_time.to_consensus_u32(),
+            vin: tx.inputs().map(|txin| BitcoindTxIn::with(txin, is_coinbase)).collect(),
+            vout: tx
+                .outputs_with_index()
+                .map(|(vout, txout)| BitcoindTxOut::with(txout, vout.into_u32()))
+                .collect(),
+        }
+    }
+}
+
+impl BitcoindTxIn {
+    fn with(txin: &TxIn, is_coinbase: bool) -> Self {
+        let txinwitness = txin.witness.elements().map(<[u8]>::to_hex).collect();
+        let sequence = txin.sequence.to_consensus_u32();
+        if is_coinbase {
+            return BitcoindTxIn {
+                coinbase: Some(txin.sig_script.as_slice().to_hex()),
+                txid: None,
+                vout: None,
+                script_sig: None,
+                txinwitness,
+                sequence,
+            };
+        }
+        BitcoindTxIn {
+            coinbase: None,
+            txid: Some(txin.prev_output.txid.to_string()),
+            vout: Some(txin.prev_output.vout.into_u32()),
+            script_sig: Some(BitcoindScriptSig {
+                asm: script_asm(ScriptRef::new(txin.sig_script.as_slice()), true),
+                hex: txin.sig_script.as_slice().to_hex(),
+            }),
+            txinwitness,
+            sequence,
+        }
+    }
+}
+
+impl BitcoindTxOut {
+    fn with(txout: &TxOut, n: u32) -> Self {
+        let script = txout.script_pubkey.as_script_ref();
+        BitcoindTxOut {
+            value: txout.value,
+            n,
+            script_pubkey: BitcoindScriptPubkey {
+                asm: script_asm(script, false),
+                hex: script.as_slice().to_hex(),
+                ty: script_type(script),
+            },
+        }
+    }
+}
+
+/// Renders script in the same way as Bitcoin Core `ScriptToAsmStr` does.
+fn script_asm(script: ScriptRef, decode_sighash: bool) -> String {
+    let unspendable = script.is_op_return();
+    let mut asm = Vec::new();
+    for instr in script.instructions() {
+        match instr {
+            Err(_) => {
+                asm.push(s!("[error]"));
+                break;
+            }
+            Ok(Instruction::PushBytes { data, .. }) if data.len() <= 4 => {
+                asm.push(script_num(data).to_string())
+            }
+            Ok(Instruction::PushBytes { data, .. }) => match sighash_suffix(data) {
+                Some(suffix) if decode_sighash && !unspendable => {
+                    asm.push(format!("{}{suffix}", data[..data.len() - 1].to_hex()))
+                }
+                _ => asm.push(data.to_hex()),
+            },
+            Ok(Instruction::Op(op)) => asm.push(op_name(op).to_owned()),
+        }
+    }
+    asm.join(" ")
+}
+
+/// Decodes minimally-sized script number (up to 4 bytes).
+fn script_num(data: &[u8]) -> i64 {
+    let Some(last) = data.last() else {
+        return 0;
+    };
+    let mut value = data
+        .iter()
+        .enumerate()
+        .fold(0i64, |acc, (index, byte)| acc | ((*byte as i64) << (8 * index)));
+    if last & 0x80 != 0 {
+        value &= !(0x80i64 << (8 * (data.len() - 1)));
+        value = -value;
+    }
+    value
+}
+
+/// Returns sighash type suffix for a data which are a strictly-encoded DER
+/// ECDSA signature with a defined sighash type.
+fn sighash_suffix(sig: &[u8]) -> Option<&'static str> {
+    if !is_valid_der_sig(sig) {
+        return None;
+    }
+    Some(match sig[sig.len() - 1] {
+        0x01 => "[ALL]",
+        0x02 => "[NONE]",
+        0x03 => "[SINGLE]",
+        0x81 => "[ALL|ANYONECANPAY]",
+        0x82 => "[NONE|ANYONECANPAY]",
+        0x83 => "[SINGLE|ANYONECANPAY]",
+        _ => return None,
+    })
+}
+
+/// Checks strict DER signature encoding (BIP-66) followed by a sighash byte.
+fn is_valid_der_sig(sig: &[u8]) -> bool {
+    let len = sig.len();
+    if !(9..=73).contains(&len) || sig[0] != 0x30 || sig[1] as usize != len - 3 {
+        return false;
+    }
+    let len_r = sig[3] as usize;
+    if 5 + len_r >= len {
+        return false;
+    }
+    let len_s = sig[5 + len_r] as usize;
+    if len_r + len_s + 7 != len {
+        return false;
+    }
+    let r = &sig[4..4 + len_r];
+    let s = &sig[6 + len_r..6 + len_r + len_s];
+    let is_valid_int = |marker: u8, int: &[u8]| {
+        marker == 0x02
+            && !int.is_empty()
+            && int[0] & 0x80 == 0
+            && !(int.len() > 1 && int[0] == 0x00 && int[1] & 0x80 == 0)
+    };
+    is_valid_int(sig[2], r) && is_valid_int(sig[4 + len_r], s)
+}
+
+/// Returns script type name as reported by Bitcoin Core.
+fn script_type(script: ScriptRef) -> &'static str {
+    let data = script.as_slice();
+    if script.is_p2sh() {
+        return "scripthash";
+    }
+    if script.is_p2wpkh() {
+        return "witness_v0_keyhash";
+    }
+    if script.is_p2wsh() {
+        return "witness_v0_scripthash";
+    }
+    if script.is_p2tr() {
+        return "witness_v1_taproot";
+    }
+    if data == [OP_PUSHNUM_1, OP_PUSHBYTES_2, 0x4e, 0x73] {
+        return "anchor";
+    }
+    if (4..=42).contains(&data.len()) && data[1] as usize == data.len() - 2 {
+        match data[0] {
+            OP_PUSHBYTES_0 => return "nonstandard",
+            OP_PUSHNUM_1..=OP_PUSHNUM_16 => return "witness_unknown",
+            _ => {}
+        }
+    }
+    // Bitcoin Core `IsPushOnly` treats all opcodes up to `OP_16` as pushes
+    if script.is_op_return()
+        && ScriptRef::new(&data[1..])
+            .instructions()
+            .all(|instr| matches!(instr, Ok(instr) if instr.opcode() <= OP_PUSHNUM_16))
+    {
+        return "nulldata";
+    }
+    if script.is_p2pk() {
+        return "pubkey";
+    }
+    if script.is_p2pkh() {
+        return "pubkeyhash";
+    }
+    if script.is_multisig() {
+        return "multisig";
+    }
+    "nonstandard"
+}
+
+/// Returns opcode name as reported by Bitcoin Core `GetOpName`.
+fn op_name(op: u8) -> &'static str {
+    match op {
+        OP_PUSHBYTES_0 => "0",
+        OP_PUSHDATA1 => "OP_PUSHDATA1",
+        OP_PUSHDATA2 => "OP_PUSHDATA2",
+        OP_PUSHDATA4 => "OP_PUSHDATA4",
+        OP_PUSHNUM_NEG1 => "-1",
+        OP_RESERVED => "OP_RESERVED",
+        OP_PUSHNUM_1 => "1",
+        OP_PUSHNUM_2 => "2",
+        OP_PUSHNUM_3 => "3",
+        OP_PUSHNUM_4 => "4",
+        OP_PUSHNUM_5 => "5",
+        OP_PUSHNUM_6 => "6",
+        OP_PUSHNUM_7 => "7",
+        OP_PUSHNUM_8 => "8",
+        OP_PUSHNUM_9 => "9",
+        OP_PUSHNUM_10 => "10",
+        OP_PUSHNUM_11 => "11",
+        OP_PUSHNUM_12 => "12",
+        OP_PUSHNUM_13 => "13",
+        OP_PUSHNUM_14 => "14",
+        OP_PUSHNUM_15 => "15",
+        OP_PUSHNUM_16 => "16",
+        OP_NOP => "OP_NOP",
+        OP_VER => "OP_VER",
+        OP_IF => "OP_IF",
+        OP_NOTIF => "OP_NOTIF",
+        OP_VERIF => "OP_VERIF",
+        OP_VERNOTIF => "OP_VERNOTIF",
+        OP_ELSE => "OP_ELSE",
+        OP_ENDIF => "OP_ENDIF",
+        OP_VERIFY => "OP_VERIFY",
+        OP_RETURN => "OP_RETURN",
+        OP_TOALTSTACK => "OP_TOALTSTACK",
+        OP_FROMALTSTACK => "OP_FROMALTSTACK",
+        OP_2DROP => "OP_2DROP",
+        OP_2DUP => "OP_2DUP",
+        OP_3DUP => "OP_3DUP",
+        OP_2OVER => "OP_2OVER",
+        OP_2ROT => "OP_2ROT",
+        OP_2SWAP => "OP_2SWAP",
+        OP_IFDUP => "OP_IFDUP",
+        OP_DEPTH => "OP_DEPTH",
+        OP_DROP => "OP_DROP",
+        OP_DUP => "OP_DUP",
+        OP_NIP => "OP_NIP",
+        OP_OVER => "OP_OVER",
+        OP_PICK => "OP_PICK",
+        OP_ROLL => "OP_ROLL",
+        OP_ROT => "OP_ROT",
+        OP_SWAP => "OP_SWAP",
+        OP_TUCK => "OP_TUCK",
+        OP_CAT => "OP_CAT",
+        OP_SUBSTR => "OP_SUBSTR",
+        OP_LEFT => "OP_LEFT",
+        OP_RIGHT => "OP_RIGHT",
+        OP_SIZE => "OP_SIZE",
+        OP_INVERT => "OP_INVERT",
+        OP_AND => "OP_AND",
+        OP_OR => "OP_OR",
+        OP_XOR => "OP_XOR",
+        OP_EQUAL => "OP_EQUAL",
+        OP_EQUALVERIFY => "OP_EQUALVERIFY",
+        OP_RESERVED1 => "OP_RESERVED1",
+        OP_RESERVED2 => "OP_RESERVED2",
+        OP_1ADD => "OP_1ADD",
+        OP_1SUB => "OP_1SUB",
+        OP_2MUL => "OP_2MUL",
+        OP_2DIV => "OP_2DIV",
+        OP_NEGATE => "OP_NEGATE",
+        OP_ABS => "OP_ABS",
+        OP_NOT => "OP_NOT",
+        OP_0NOTEQUAL => "OP_0NOTEQUAL",
+        OP_ADD => "OP_ADD",
+        OP_SUB => "OP_SUB",
+        OP_MUL => "OP_MUL",
+        OP_DIV => "OP_DIV",
+        OP_MOD => "OP_MOD",
+        OP_LSHIFT => "OP_LSHIFT",
+        OP_RSHIFT => "OP_RSHIFT",
+        OP_BOOLAND => "OP_BOOLAND",
+        OP_BOOLOR => "OP_BOOLOR",
+        OP_NUMEQUAL => "OP_NUMEQUAL",
+        OP_NUMEQUALVERIFY => "OP_NUMEQUALVERIFY",
+        OP_NUMNOTEQUAL => "OP_NUMNOTEQUAL",
+        OP_LESSTHAN => "OP_LESSTHAN",
+        OP_GREATERTHAN => "OP_GREATERTHAN",
+        OP_LESSTHANOREQUAL => "OP_LESSTHANOREQUAL",
+        OP_GREATERTHANOREQUAL => "OP_GREATERTHANOREQUAL",
+        OP_MIN => "OP_MIN",
+        OP_MAX => "OP_MAX",
+        OP_WITHIN => "OP_WITHIN",
+        OP_RIPEMD160 => "OP_RIPEMD160",
+        OP_SHA1 => "OP_SHA1",
+        OP_SHA256 => "OP_SHA256",
+        OP_HASH160 => "OP_HASH160",
+        OP_HASH256 => "OP_HASH256",
+        OP_CODESEPARATOR => "OP_CODESEPARATOR",
+        OP_CHECKSIG => "OP_CHECKSIG",
+        OP_CHECKSIGVERIFY => "OP_CHECKSIGVERIFY",
+        OP_CHECKMULTISIG => "OP_CHECKMULTISIG",
+        OP_CHECKMULTISIGVERIFY => "OP_CHECKMULTISIGVERIFY",
+        OP_NOP1 => "OP_NOP1",
+        OP_CLTV => "OP_CHECKLOCKTIMEVERIFY",
+        OP_CSV => "OP_CHECKSEQUENCEVERIFY",
+        OP_NOP4 => "OP_NOP4",
+        OP_NOP5 => "OP_NOP5",
+        OP_NOP6 => "OP_NOP6",
+        OP_NOP7 => "OP_NOP7",
+        OP_NOP8 => "OP_NOP8",
+        OP_NOP9 => "OP_NOP9",
+        OP_NOP10 => "OP_NOP10",
+        OP_CHECKSIGADD => "OP_CHECKSIGADD",
+        OP_INVALIDOPCODE => "OP_INVALIDOPCODE",
+        _ => "OP_UNKNOWN",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::hex::FromHex;
+
+    use super::*;
+    use crate::ScriptPubkey;
+
+    #[test]
+    fn script_num_decoding() {
+        assert_eq!(script_num(&[]), 0);
+        assert_eq!(script_num(&[0x01]), 1);
+        assert_eq!(script_num(&[0x81]), -1);
+        assert_eq!(script_num(&[0xff, 0x00]), 255);
+        assert_eq!(script_num(&[0x10, 0x27, 0x00, 0x80]), -10000);
+    }
+
+    #[test]
+    fn bitcoind_json() {
+        // Unsigned transaction from BIP-143 native P2WPKH example
+        let tx = Tx::from_str(
+            "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000\
+             00eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a01000000\
+             00ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac90\
+             93510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000",
+        )
+        .unwrap();
+        let json = serde_json::to_value(BitcoindTx::from(&tx)).unwrap();
+        assert_eq!(json["txid"], json["hash"]);
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["size"], 160);
+        assert_eq!(json["vsize"], 160);
+        assert_eq!(json["weight"], 640);
+        assert_eq!(json["locktime"], 17);
+        assert_eq!(json["vin"][0]["vout"], 0);
+        assert_eq!(json["vin"][0]["sequence"], 0xffffffeeu32);
+        assert_eq!(json["vin"][0]["scriptSig"]["asm"], "");
+        assert!(json["vin"][0].get("txinwitness").is_none());
+        assert!(json["vin"][0].get("coinbase").is_none());
+        assert_eq!(json["vout"][1]["n"], 1);
+        assert_eq!(json["vout"][1]["value"], 2.2345);
+        assert_eq!(json["vout"][0]["scriptPubKey"]["type"], "pubkeyhash");
+        assert_eq!(
+            json["vout"][0]["scriptPubKey"]["asm"],
+            "OP_DUP OP_HASH160 8280b37df378db99f66f85c95a783a76ac7a6d59 OP_EQUALVERIFY OP_CHECKSIG"
+        );
+    }
+
+    #[test]
+    fn btc_amounts() {
+        let value = |sats: u64| {
+            serde_json::to_value(BitcoindTxOut::with(&TxOut::new(ScriptPubkey::new(), sats), 0))
+                .unwrap()["value"]
+                .clone()
+        };
+        assert_eq!(value(0), 0.0);
+        assert_eq!(value(1), 0.00000001);
+        assert_eq!(value(223_450_000), 2.2345);
+        assert_eq!(value(2_099_999_997_690_000), 20999999.9769);
+    }
+
+    #[test]
+    fn script_types() {
+        let mut hybrid = vec![OP_PUSHBYTES_65, 0x06];
+        hybrid.extend([0xAB; 64]);
+        hybrid.push(OP_CHECKSIG);
+        assert_eq!(script_type(ScriptRef::new(&hybrid)), "pubkey");
+
+        let mut multisig = vec![OP_PUSHNUM_1, OP_PUSHBYTES_33, 0x02];
+        multisig.extend([0xAB; 32]);
+        multisig.extend([OP_PUSHNUM_1, OP_CHECKMULTISIG]);
+        assert_eq!(script_type(ScriptRef::new(&multisig)), "multisig");
+        multisig[0] = OP_PUSHNUM_2;
+        assert_eq!(script_type(ScriptRef::new(&multisig)), "nonstandard");
+
+        for nulldata in [
+            &[OP_RETURN][..],
+            &[OP_RETURN, OP_PUSHBYTES_2, 0xAB, 0xCD],
+            &[OP_RETURN, OP_PUSHNUM_1],
+            &[OP_RETURN, OP_PUSHNUM_NEG1, OP_PUSHNUM_16],
+            &[OP_RETURN, OP_RESERVED, OP_PUSHBYTES_0],
+        ] {
+            assert_eq!(script_type(ScriptRef::new(nulldata)), "nulldata");
+        }
+        assert_eq!(script_type(ScriptRef::new(&[OP_RETURN, OP_NOP])), "nonstandard");
+        assert_eq!(script_type(ScriptRef::new(&[OP_RETURN, OP_PUSHBYTES_2, 0xAB])), "nonstandard");
+    }
+
+    #[test]
+    fn sig_script_asm() {
+        let sig = "3044022003d09a2dcd8f24fdb1ba1d8e9ad82ba5a2d68b5e4b4c3d6e9b5f5a1c3b4c30e102203c1\
+                   b2a0a1a0f7c5a9e6d0b7a8e0d3c2b1a0f9e8d7c6b5a4f3e2d1c0b9a8f7e6d01";
+        let mut script = vec![0x47];
+        script.extend(Vec::<u8>::from_hex(sig).unwrap());
+        script.extend([OP_PUSHNUM_2, OP_PUSHBYTES_2, 0xe8, 0x03]);
+        assert_eq!(
+            script_asm(ScriptRef::new(&script), true),
+            format!("{}[ALL] 2 1000", &sig[..sig.len() - 2])
+        );
+        assert_eq!(script_asm(ScriptRef::new(&[OP_PUSHBYTES_2, 0x01]), false), "[error]");
+    }
+}
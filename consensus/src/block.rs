@@ -23,11 +23,12 @@ use std::fmt;
 use std::fmt::{Formatter, LowerHex};
 use std::str::FromStr;
 
-use amplify::hex::{FromHex, ToHex};
 use amplify::{ByteArray, Bytes32StrRev, Wrapper};
-use commit_verify::{DigestExt, Sha256};
 
-use crate::{BlockDataParseError, ConsensusDecode, ConsensusEncode, LIB_NAME_BITCOIN};
+use crate::{
+    BlockDataParseError, ConsensusDecode, ConsensusEncode, Sha256d, Tx, Txid, VarIntArray,
+    WitnessCommitment, LIB_NAME_BITCOIN,
+};
 
 #[derive(Wrapper, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, From)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -73,32 +74,393 @@ pub struct BlockHeader {
 
 impl LowerHex for BlockHeader {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.consensus_serialize().to_hex())
+        f.write_str(&self.consensus_serialize_hex())
     }
 }
 
 impl FromStr for BlockHeader {
     type Err = BlockDataParseError;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let data = Vec::<u8>::from_hex(s)?;
-        BlockHeader::consensus_deserialize(data).map_err(BlockDataParseError::from)
-    }
+    fn from_str(s: &str) -> Result<Self, Self::Err> { BlockHeader::consensus_deserialize_hex(s) }
 }
 
 impl BlockHeader {
+    /// Constructs block header with a zero nonce.
+    pub fn new(
+        version: i32,
+        prev_block_hash: BlockHash,
+        merkle_root: BlockMerkleRoot,
+        time: u32,
+        bits: u32,
+    ) -> Self {
+        BlockHeader {
+            version,
+            prev_block_hash,
+            merkle_root,
+            time,
+            bits,
+            nonce: 0,
+        }
+    }
+
+    /// Decodes the compact `bits` representation of the proof-of-work target
+    /// into a 256-bit big-endian number.
+    ///
+    /// Returns `None` if the compact value is negative or overflows 256 bits.
+    pub fn target(&self) -> Option<[u8; 32]> {
+        if self.bits & 0x0080_0000 != 0 {
+            return None;
+        }
+        let exp = (self.bits >> 24) as usize;
+        let mut target = [0u8; 32];
+        // Mantissa byte `i` has weight of `256^(exp - 1 - i)`
+        for (i, byte) in self.bits.to_be_bytes()[1..].iter().enumerate() {
+            let Some(pos) = (exp + 2).checked_sub(i + 3) else {
+                continue;
+            };
+            match pos {
+                _ if *byte == 0 => {}
+                0..=31 => target[31 - pos] = *byte,
+                _ => return None,
+            }
+        }
+        Some(target)
+    }
+
+    /// Checks whether the block hash is not above the `target`, provided as a
+    /// 256-bit big-endian number.
+    pub fn meets_target(&self, target: [u8; 32]) -> bool {
+        let mut hash = self.block_hash().to_byte_array();
+        hash.reverse();
+        hash <= target
+    }
+
+    /// Increments the nonce, starting from its current value, until the block
+    /// hash meets the `target`, provided as a 256-bit big-endian number.
+    ///
+    /// Returns `false` if no nonce satisfies the target; in this case the nonce
+    /// is left at `u32::MAX`. Intended for low-difficulty networks, like
+    /// regtest.
+    pub fn mine(&mut self, target: [u8; 32]) -> bool {
+        loop {
+            if self.meets_target(target) {
+                return true;
+            }
+            if self.nonce == u32::MAX {
+                return false;
+            }
+            self.nonce += 1;
+        }
+    }
+
+    /// Serializes the header into its fixed-size 80-byte consensus
+    /// representation.
+    pub fn to_array(&self) -> [u8; 80] {
+        let mut data = [0u8; 80];
+        data[..4].copy_from_slice(&self.version.to_le_bytes());
+        data[4..36].copy_from_slice(&self.prev_block_hash.to_byte_array());
+        data[36..68].copy_from_slice(&self.merkle_root.to_byte_array());
+        data[68..72].copy_from_slice(&self.time.to_le_bytes());
+        data[72..76].copy_from_slice(&self.bits.to_le_bytes());
+        data[76..].copy_from_slice(&self.nonce.to_le_bytes());
+        data
+    }
+
+    /// Deserializes the header from its fixed-size 80-byte consensus
+    /// representation.
+    pub fn from_array(data: [u8; 80]) -> Self {
+        let u32_at = |pos: usize| {
+            u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+        };
+        let mut prev_block_hash = [0u8; 32];
+        prev_block_hash.copy_from_slice(&data[4..36]);
+        let mut merkle_root = [0u8; 32];
+        merkle_root.copy_from_slice(&data[36..68]);
+        BlockHeader {
+            version: u32_at(0) as i32,
+            prev_block_hash: prev_block_hash.into(),
+            merkle_root: merkle_root.into(),
+            time: u32_at(68),
+            bits: u32_at(72),
+            nonce: u32_at(76),
+        }
+    }
+
     pub fn block_hash(&self) -> BlockHash {
-        let mut enc = Sha256::default();
-        self.consensus_encode(&mut enc).expect("engines don't error");
-        let mut double = Sha256::default();
-        double.input_raw(&enc.finish());
-        BlockHash::from_byte_array(double.finish())
+        BlockHash::from_byte_array(Sha256d::digest(self.to_array()))
+    }
+}
+
+/// Bitcoin block: block header followed by the list of block transactions.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = LIB_NAME_BITCOIN)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct Block {
+    /// Block header.
+    pub header: BlockHeader,
+    /// List of transactions included into the block, starting with the
+    /// coinbase transaction.
+    pub transactions: VarIntArray<Tx>,
+}
+
+impl LowerHex for Block {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.consensus_serialize_hex())
+    }
+}
+
+impl FromStr for Block {
+    type Err = BlockDataParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Block::consensus_deserialize_hex(s) }
+}
+
+impl Block {
+    #[inline]
+    pub fn block_hash(&self) -> BlockHash { self.header.block_hash() }
+
+    /// Iterates over the block transactions together with their ids, computing
+    /// each id once.
+    pub fn transactions(&self) -> impl Iterator<Item = (Txid, &Tx)> {
+        self.transactions.iter().map(|tx| (tx.txid(), tx))
+    }
+
+    /// Converts the block into an iterator over its transactions together with
+    /// their ids, computing each id once.
+    pub fn into_transactions(self) -> impl Iterator<Item = (Txid, Tx)> {
+        self.transactions.into_iter().map(|tx| (tx.txid(), tx))
+    }
+
+    /// Computes merkle root of the block transactions. For a block without
+    /// transactions returns all-zero hash.
+    pub fn merkle_root(&self) -> BlockMerkleRoot {
+        let mut layer = self.txid_layer();
+        if layer.is_empty() {
+            return BlockMerkleRoot::from_byte_array([0u8; 32]);
+        }
+        while layer.len() > 1 {
+            layer = merkle_layer_up(&layer);
+        }
+        BlockMerkleRoot::from_byte_array(layer[0])
+    }
+
+    /// Constructs merkle inclusion proof for a transaction with a given id.
+    ///
+    /// Returns list of sibling hashes, starting from the leaf level, and the
+    /// index of the transaction in the block; or `None` if the block doesn't
+    /// contain the transaction. The proof can be checked with
+    /// [`verify_merkle_proof`].
+    pub fn merkle_proof(&self, txid: Txid) -> Option<(Vec<[u8; 32]>, u32)> {
+        let mut layer = self.txid_layer();
+        let index = layer.iter().position(|id| *id == txid.to_byte_array())?;
+        let mut siblings = vec![];
+        let mut pos = index;
+        while layer.len() > 1 {
+            let sibling = layer.get(pos ^ 1).unwrap_or(&layer[pos]);
+            siblings.push(*sibling);
+            layer = merkle_layer_up(&layer);
+            pos >>= 1;
+        }
+        Some((siblings, index as u32))
+    }
+
+    /// Computes merkle root of the block transaction witness ids (BIP-141),
+    /// in which the coinbase transaction is represented by an all-zero hash.
+    /// For a block without transactions returns all-zero hash.
+    pub fn witness_root(&self) -> [u8; 32] {
+        let mut layer = self
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(no, tx)| if no == 0 { [0u8; 32] } else { tx.wtxid().to_byte_array() })
+            .collect::<Vec<_>>();
+        if layer.is_empty() {
+            return [0u8; 32];
+        }
+        while layer.len() > 1 {
+            layer = merkle_layer_up(&layer);
+        }
+        layer[0]
+    }
+
+    /// Returns witness commitment from the coinbase transaction, if present.
+    ///
+    /// If several coinbase outputs contain a commitment, the one with the
+    /// highest output number is used, as required by BIP-141.
+    pub fn witness_commitment(&self) -> Option<WitnessCommitment> {
+        self.transactions
+            .first()?
+            .outputs()
+            .rev()
+            .find_map(|txout| WitnessCommitment::from_script_pubkey(&txout.script_pubkey))
     }
+
+    /// Validates the block witness commitment.
+    ///
+    /// A block without the witness commitment is valid only if none of its
+    /// transactions has witness data. Otherwise, the coinbase input witness
+    /// must consist of a single 32-byte reserved value, and the commitment
+    /// must match the one computed from [`Block::witness_root`] and the
+    /// reserved value.
+    pub fn verify_witness_commitment(&self) -> bool {
+        let Some(commitment) = self.witness_commitment() else {
+            return !self.transactions.iter().any(Tx::is_segwit);
+        };
+        let Some(coinbase_input) = self.transactions.first().and_then(|tx| tx.inputs().next())
+        else {
+            return false;
+        };
+        let mut elements = coinbase_input.witness.elements();
+        let (Some(reserved_value), None) = (elements.next(), elements.next()) else {
+            return false;
+        };
+        let Ok(reserved_value) = <[u8; 32]>::try_from(reserved_value) else {
+            return false;
+        };
+        WitnessCommitment::compute(self.witness_root(), reserved_value) == commitment
+    }
+
+    fn txid_layer(&self) -> Vec<[u8; 32]> {
+        self.transactions.iter().map(|tx| tx.txid().to_byte_array()).collect()
+    }
+}
+
+/// Verifies merkle inclusion proof for a transaction produced by
+/// [`Block::merkle_proof`] against the block merkle root.
+///
+/// NB: Bitcoin merkle tree duplicates the last hash at odd-sized levels, thus
+/// the proof alone does not prove the number of transactions in the block
+/// (see CVE-2012-2459). The function only rejects proofs having `index`
+/// which does not fit into the tree depth defined by the number of siblings.
+pub fn verify_merkle_proof(
+    txid: Txid,
+    siblings: &[[u8; 32]],
+    index: u32,
+    root: BlockMerkleRoot,
+) -> bool {
+    if siblings.len() < 32 && index >> siblings.len() != 0 {
+        return false;
+    }
+    let mut hash = txid.to_byte_array();
+    let mut pos = index;
+    for sibling in siblings {
+        hash = if pos & 1 == 0 { merkle_node(&hash, sibling) } else { merkle_node(sibling, &hash) };
+        pos >>= 1;
+    }
+    hash == root.to_byte_array()
+}
+
+fn merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut enc = Sha256d::new();
+    enc.input_raw(left);
+    enc.input_raw(right);
+    enc.finish()
+}
+
+fn merkle_layer_up(layer: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    layer.chunks(2).map(|pair| merkle_node(&pair[0], pair.get(1).unwrap_or(&pair[0]))).collect()
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{LockTime, Outpoint, ScriptPubkey, SeqNo, TxIn, TxOut, TxVer, Vout, Witness};
+
+    fn block_with_txs(count: u8) -> Block {
+        let header = BlockHeader::from_str(
+            "00006020333eaffe61bc29a9a387aa56bd424b3c73ebb536cc4a03000000000000000000\
+             af225b062c7acf90aac833cc4e0789f17b13ef53564cdd3b748e7897d7df20ff25bcf665595a03170bcd54ad",
+        )
+        .unwrap();
+        let mut transactions: VarIntArray<Tx> = none!();
+        for no in 0..count {
+            let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+            tx.push_output(TxOut::new(ScriptPubkey::op_return(&[no]), 0u64)).unwrap();
+            transactions.push(tx).unwrap();
+        }
+        Block {
+            header,
+            transactions,
+        }
+    }
+
+    #[test]
+    fn merkle_proof() {
+        for count in 1..=7 {
+            let block = block_with_txs(count);
+            let root = block.merkle_root();
+            for tx in &block.transactions {
+                let txid = tx.txid();
+                let (siblings, index) = block.merkle_proof(txid).unwrap();
+                assert!(verify_merkle_proof(txid, &siblings, index, root));
+                if siblings.first().is_some_and(|sibling| *sibling != txid.to_byte_array()) {
+                    assert!(!verify_merkle_proof(txid, &siblings, index ^ 1, root));
+                }
+                assert!(!verify_merkle_proof(txid, &siblings, 1 << siblings.len(), root));
+            }
+        }
+        let block = block_with_txs(1);
+        assert_eq!(
+            block.merkle_root().to_byte_array(),
+            block.transactions[0].txid().to_byte_array()
+        );
+        assert_eq!(block.merkle_proof(Txid::coinbase()), None);
+    }
+
+    #[test]
+    fn transactions_with_ids() {
+        let block = block_with_txs(3);
+        let txids = block.transactions.iter().map(Tx::txid).collect::<Vec<_>>();
+        assert_eq!(block.transactions().map(|(txid, _)| txid).collect::<Vec<_>>(), txids);
+        assert!(block.transactions().all(|(txid, tx)| tx.txid() == txid));
+
+        let expected = block.transactions.iter().cloned().zip(txids).collect::<Vec<_>>();
+        assert_eq!(
+            block.into_transactions().map(|(txid, tx)| (tx, txid)).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn witness_commitment() {
+        let mut block = block_with_txs(3);
+        assert!(block.witness_commitment().is_none());
+        assert!(block.verify_witness_commitment());
+
+        let segwit_input = |witness: Vec<u8>| TxIn {
+            prev_output: Outpoint::new(Txid::coinbase(), Vout::from_u32(0)),
+            sig_script: none!(),
+            sequence: SeqNo::FINAL,
+            witness: Witness::from_consensus_stack([witness]),
+        };
+        let mut transactions = block.transactions.release();
+        transactions[0].push_input(segwit_input(vec![0u8; 32])).unwrap();
+        transactions[1].push_input(segwit_input(vec![0xAA; 72])).unwrap();
+        block.transactions = VarIntArray::from_checked(transactions.clone());
+        assert!(!block.verify_witness_commitment());
+
+        let commitment = WitnessCommitment::compute(block.witness_root(), [0u8; 32]);
+        transactions[0].push_output(TxOut::new(commitment.to_script_pubkey(), 0u64)).unwrap();
+        block.transactions = VarIntArray::from_checked(transactions.clone());
+        assert_eq!(block.witness_commitment(), Some(commitment));
+        assert!(block.verify_witness_commitment());
+
+        // Witness root doesn't depend on the coinbase witness, but the commitment does
+        let witness_root = block.witness_root();
+        transactions[0] = {
+            let mut coinbase = Tx::new(TxVer::V2, LockTime::ZERO);
+            coinbase.push_input(segwit_input(vec![1u8; 32])).unwrap();
+            for txout in block.transactions[0].outputs() {
+                coinbase.push_output(txout.clone()).unwrap();
+            }
+            coinbase
+        };
+        block.transactions = VarIntArray::from_checked(transactions);
+        assert_eq!(block.witness_root(), witness_root);
+        assert_eq!(block.witness_commitment(), Some(commitment));
+        assert!(!block.verify_witness_commitment());
+    }
 
     #[test]
     // block height 835056
@@ -119,9 +481,66 @@ mod test {
         assert_eq!(header.nonce, 0xad54cd0b);
         assert_eq!(header.time, 1710668837);
         assert_eq!(header.to_string(), header_str);
+        assert_eq!(header.to_array().to_vec(), header.consensus_serialize());
+        assert_eq!(BlockHeader::from_array(header.to_array()), header);
         assert_eq!(
             header.block_hash().to_string(),
             "00000000000000000000a885d748631afdf2408d2db66e616e963d08c31a65df"
         );
     }
+
+    #[test]
+    fn target() {
+        let header = |bits: u32| {
+            BlockHeader::new(
+                1,
+                BlockHash::from([0u8; 32]),
+                BlockMerkleRoot::from([0u8; 32]),
+                0,
+                bits,
+            )
+        };
+        let mut expected = [0u8; 32];
+        expected[4..6].copy_from_slice(&[0xFF, 0xFF]);
+        assert_eq!(header(0x1d00ffff).target(), Some(expected));
+        let mut expected = [0u8; 32];
+        expected[..3].copy_from_slice(&[0x7F, 0xFF, 0xFF]);
+        assert_eq!(header(0x207fffff).target(), Some(expected));
+        let mut expected = [0u8; 32];
+        expected[31] = 0x12;
+        assert_eq!(header(0x01123456).target(), Some(expected));
+        assert_eq!(header(0x04923456).target(), None);
+        assert_eq!(header(0x21010000).target(), None);
+
+        let header = BlockHeader::from_str(
+            "00006020333eaffe61bc29a9a387aa56bd424b3c73ebb536cc4a03000000000000000000\
+             af225b062c7acf90aac833cc4e0789f17b13ef53564cdd3b748e7897d7df20ff25bcf665595a03170bcd54ad",
+        )
+        .unwrap();
+        assert!(header.meets_target(header.target().unwrap()));
+    }
+
+    #[test]
+    fn mine() {
+        let mut header = BlockHeader::new(
+            0x20000000,
+            BlockHash::from([0x11; 32]),
+            BlockMerkleRoot::from([0x22; 32]),
+            1296688602,
+            0x207fffff,
+        );
+        assert_eq!(header.nonce, 0);
+        let target = header.target().unwrap();
+        assert!(header.mine(target));
+        assert!(header.meets_target(target));
+        let nonce = header.nonce;
+        for n in 0..nonce {
+            header.nonce = n;
+            assert!(!header.meets_target(target));
+        }
+
+        header.nonce = u32::MAX;
+        assert!(!header.mine([0u8; 32]));
+        assert_eq!(header.nonce, u32::MAX);
+    }
 }
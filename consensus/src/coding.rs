@@ -19,15 +19,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::borrow::Borrow;
 use std::io::{self, Cursor, Read, Write};
+use std::iter;
 
 use amplify::confinement::{Confined, MediumBlob, SmallBlob, TinyBlob, U32};
+use amplify::hex::{FromHex, ToHex};
 use amplify::{confinement, ByteArray, Bytes32, IoError, Wrapper};
 
 use crate::{
-    Annex, BlockHash, BlockHeader, BlockMerkleRoot, ControlBlock, InternalPk, InvalidLeafVer,
-    LeafVer, LockTime, Outpoint, Parity, RedeemScript, Sats, ScriptBytes, ScriptPubkey, SeqNo,
-    SigScript, Sighash, TapBranchHash, TapLeafHash, TapMerklePath, TapScript, Tx, TxIn, TxOut,
+    Annex, Block, BlockDataParseError, BlockHash, BlockHeader, BlockMerkleRoot, ControlBlock,
+    InternalPk, InvalidLeafVer, LeafScript, LeafVer, LockTime, Outpoint, Parity, RedeemScript,
+    Sats, ScriptBytes, ScriptPubkey, SeqNo, SigScript, Sighash, TapBranchHash, TapLeafHash,
+    TapMerklePath, TapNodeHash, TapScript, TapTree, TapTreeError, TapTreeLeaf, Tx, TxIn, TxOut,
     TxVer, Txid, Vout, Witness, WitnessScript, LIB_NAME_BITCOIN, TAPROOT_ANNEX_PREFIX,
 };
 
@@ -36,6 +40,12 @@ use crate::{
 /// block data structure to exceed 2^32 bytes (4GB), and any change to that rule
 /// will be a hardfork. So for practical reasons we are safe to restrict the
 /// maximum size here with just 32 bits.
+///
+/// Since the array is a [`Confined`] collection, it can't be mutated in a way
+/// breaking the size limits. Use fallible `Confined::push`, `Confined::extend`
+/// and `Confined::try_from_iter` to build arrays (like transaction inputs,
+/// outputs or witness stacks) incrementally; they return
+/// [`confinement::Error`] once the array would exceed 2^32 elements.
 pub type VarIntArray<T, const MIN_LEN: usize = 0> = Confined<Vec<T>, MIN_LEN, U32>;
 
 pub type VarIntBytes<const MIN_LEN: usize = 0> = Confined<Vec<u8>, MIN_LEN, U32>;
@@ -116,6 +126,35 @@ impl From<MediumBlob> for ByteStr {
 }
 
 impl ByteStr {
+    /// Constructs an empty byte string with the given pre-allocated capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self { Self(Confined::with_capacity(capacity)) }
+
+    /// Tries to construct a byte string from a byte vector. Fails if the
+    /// vector length exceeds 4GB.
+    // We can't use `impl TryFrom` due to the conflict with core library blanked
+    // implementation
+    #[inline]
+    pub fn try_from(bytes: Vec<u8>) -> Result<Self, confinement::Error> {
+        Confined::try_from(bytes).map(Self)
+    }
+
+    /// Adds a single byte to the end of the byte string. Fails if the byte
+    /// string length exceeds 4GB.
+    #[inline]
+    pub fn push(&mut self, byte: u8) -> Result<(), confinement::Error> { self.0.push(byte) }
+
+    /// Appends all bytes from the slice to the end of the byte string. Fails
+    /// if the resulting byte string length exceeds 4GB, leaving the byte
+    /// string unmodified.
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), confinement::Error> {
+        let len = self.len() + bytes.len();
+        if len > U32 {
+            return Err(confinement::Error::Oversize { len, max_len: U32 });
+        }
+        self.0.extend(bytes.iter().copied())
+    }
+
     pub fn len_var_int(&self) -> VarInt { VarInt(self.len() as u64) }
 
     pub fn into_vec(self) -> Vec<u8> { self.0.release() }
@@ -162,6 +201,20 @@ pub enum ConsensusDecodeError {
     #[from(io::Error)]
     Io(IoError),
 
+    /// I/O error happened while reading data at a known byte offset (for
+    /// instance, the data are truncated).
+    ///
+    /// Returned by [`ConsensusDecode::consensus_deserialize`], where the
+    /// offset of the field which failed to read is tracked.
+    #[display("{error} at byte offset {offset}")]
+    IoAt {
+        /// Offset of the field which failed to read from the start of the
+        /// data.
+        offset: usize,
+        /// The underlying I/O error.
+        error: IoError,
+    },
+
     #[display(inner)]
     #[from]
     #[from(InvalidLeafVer)]
@@ -199,8 +252,51 @@ pub enum ConsensusDataError {
     #[display(inner)]
     Confined(confinement::Error),
 
-    /// unsupported Segwit flag {0}.
-    UnsupportedSegwitFlag(u8),
+    /// unsupported Segwit flag {flag:#04x} at byte offset {offset} of the
+    /// transaction data; transaction inputs were not read.
+    UnsupportedSegwitFlag {
+        /// Value of the unsupported flag byte.
+        flag: u8,
+        /// Offset of the flag byte from the start of the transaction data.
+        offset: usize,
+    },
+
+    /// transaction is serialized with Segwit marker and flag, but has no
+    /// non-empty input witnesses.
+    SuperfluousWitnessFlag,
+
+    /// consensus data exceed the maximum allowed size of {max} bytes.
+    DataTooLarge {
+        /// Maximum size of the data allowed by the caller.
+        max: usize,
+    },
+}
+
+/// Reader tracking the offset of the data it has successfully read.
+///
+/// Since failed `read_exact` calls don't advance the offset, on error it points
+/// to the start of the field which was failed to read.
+struct OffsetReader<R: Read> {
+    inner: R,
+    offset: usize,
+}
+
+impl<R: Read> OffsetReader<R> {
+    fn new(inner: R) -> Self { Self { inner, offset: 0 } }
+}
+
+impl<R: Read> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.offset += len;
+        Ok(len)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.offset += buf.len();
+        Ok(())
+    }
 }
 
 pub trait ConsensusEncode {
@@ -210,6 +306,7 @@ pub trait ConsensusEncode {
         self.consensus_encode(&mut buf).expect("in-memory writing can't fail");
         buf
     }
+    fn consensus_serialize_hex(&self) -> String { self.consensus_serialize().to_hex() }
 }
 
 pub trait ConsensusDecode
@@ -217,13 +314,117 @@ where Self: Sized
 {
     fn consensus_decode(reader: &mut impl Read) -> Result<Self, ConsensusDecodeError>;
     fn consensus_deserialize(bytes: impl AsRef<[u8]>) -> Result<Self, ConsensusDecodeError> {
-        let mut cursor = Cursor::new(bytes.as_ref());
-        let me = Self::consensus_decode(&mut cursor)?;
-        if cursor.position() as usize != bytes.as_ref().len() {
+        let bytes = bytes.as_ref();
+        let mut reader = OffsetReader::new(bytes);
+        let me = Self::consensus_decode(&mut reader).map_err(|err| match err {
+            ConsensusDecodeError::Io(error) => ConsensusDecodeError::IoAt {
+                offset: reader.offset,
+                error,
+            },
+            err => err,
+        })?;
+        if reader.offset != bytes.len() {
             return Err(ConsensusDataError::DataNotConsumed.into());
         }
         Ok(me)
     }
+    /// Decodes the value from a reader, which must not contain any data after
+    /// it, providing streaming readers with the same guarantees as
+    /// [`ConsensusDecode::consensus_deserialize`] gives for byte buffers.
+    ///
+    /// After decoding, tries to read one more byte and fails with
+    /// [`ConsensusDataError::DataNotConsumed`] if the reader is not at its
+    /// end. NB: for readers like network sockets this means the call blocks
+    /// until the other side closes the stream.
+    fn consensus_decode_exact(reader: &mut impl Read) -> Result<Self, ConsensusDecodeError> {
+        let me = Self::consensus_decode(reader)?;
+        let mut buf = [0u8; 1];
+        loop {
+            return match reader.read(&mut buf) {
+                Ok(0) => Ok(me),
+                Ok(_) => Err(ConsensusDataError::DataNotConsumed.into()),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => Err(err.into()),
+            };
+        }
+    }
+    /// Decodes the value from an untrusted reader, reading at most `max_len`
+    /// bytes from it.
+    ///
+    /// The limit applies to the reader passed to all nested decoders. Thus
+    /// length-prefixed data (collections, scripts, witness stacks) can't make
+    /// the decoder read, allocate or process more than `max_len` bytes,
+    /// whatever length they declare: decoding fails with
+    /// [`ConsensusDataError::DataTooLarge`] once the limit is reached.
+    fn consensus_decode_bounded(
+        reader: &mut impl Read,
+        max_len: usize,
+    ) -> Result<Self, ConsensusDecodeError> {
+        let mut reader = reader.take(max_len as u64);
+        Self::consensus_decode(&mut reader).map_err(|err| match err {
+            ConsensusDecodeError::Io(_) if reader.limit() == 0 => {
+                ConsensusDataError::DataTooLarge { max: max_len }.into()
+            }
+            err => err,
+        })
+    }
+    fn consensus_deserialize_hex(hex: &str) -> Result<Self, BlockDataParseError> {
+        let data = Vec::<u8>::from_hex(hex)?;
+        Self::consensus_deserialize(data).map_err(BlockDataParseError::from)
+    }
+}
+
+/// Serializes a set of unspent transaction outputs (UTXO snapshot).
+///
+/// The format is a [`VarInt`] number of entries followed by the entries, each
+/// being a consensus-encoded [`Outpoint`] followed by a consensus-encoded
+/// [`TxOut`]. The order of the entries is preserved. Use [`decode_utxo_set`]
+/// to read the data back.
+pub fn encode_utxo_set<O: Borrow<Outpoint>, T: Borrow<TxOut>>(
+    utxos: impl IntoIterator<Item = (O, T)>,
+) -> Vec<u8> {
+    let mut count = 0usize;
+    let mut entries = Vec::new();
+    for (outpoint, txout) in utxos {
+        outpoint.borrow().consensus_encode(&mut entries).expect("in-memory writing can't fail");
+        txout.borrow().consensus_encode(&mut entries).expect("in-memory writing can't fail");
+        count += 1;
+    }
+    let mut buf = VarInt::with(count).consensus_serialize();
+    buf.extend(entries);
+    buf
+}
+
+/// Deserializes a set of unspent transaction outputs produced by
+/// [`encode_utxo_set`].
+///
+/// Reads the number of entries immediately; the entries themselves are read
+/// lazily by the returned iterator. The iterator stops after the first error.
+/// Data following the last entry are not consumed from the reader.
+///
+/// # Errors
+///
+/// If the number of entries can't be read.
+pub fn decode_utxo_set<R: Read>(
+    mut reader: R,
+) -> Result<
+    impl Iterator<Item = Result<(Outpoint, TxOut), ConsensusDecodeError>>,
+    ConsensusDecodeError,
+> {
+    let mut remaining = VarInt::consensus_decode(&mut reader)?.to_u64();
+    Ok(iter::from_fn(move || {
+        if remaining == 0 {
+            return None;
+        }
+        remaining -= 1;
+        let utxo = Outpoint::consensus_decode(&mut reader).and_then(|outpoint| {
+            TxOut::consensus_decode(&mut reader).map(|txout| (outpoint, txout))
+        });
+        if utxo.is_err() {
+            remaining = 0;
+        }
+        Some(utxo)
+    }))
 }
 
 impl ConsensusEncode for BlockHeader {
@@ -257,6 +458,25 @@ impl ConsensusDecode for BlockHeader {
     }
 }
 
+impl ConsensusEncode for Block {
+    fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
+        let mut counter = self.header.consensus_encode(writer)?;
+        counter += self.transactions.consensus_encode(writer)?;
+        Ok(counter)
+    }
+}
+
+impl ConsensusDecode for Block {
+    fn consensus_decode(reader: &mut impl Read) -> Result<Self, ConsensusDecodeError> {
+        let header = BlockHeader::consensus_decode(reader)?;
+        let transactions = VarIntArray::<Tx>::consensus_decode(reader)?;
+        Ok(Block {
+            header,
+            transactions,
+        })
+    }
+}
+
 impl ConsensusEncode for BlockHash {
     fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
         writer.write_all(&self.to_byte_array())?;
@@ -310,15 +530,18 @@ impl ConsensusDecode for Tx {
 
         let segwit = prefix == 0u8;
         let mut inputs = if segwit {
-            // SegWit
+            // SegWit: the flag follows 4-byte version and 1-byte marker. We
+            // fail before reading any of the inputs, so the rest of the
+            // stream is left untouched.
             let flag = u8::consensus_decode(reader)?;
             if flag != 0x01 {
-                Err(ConsensusDataError::UnsupportedSegwitFlag(flag))?
+                Err(ConsensusDataError::UnsupportedSegwitFlag { flag, offset: 5 })?
             }
             VarIntArray::<TxIn>::consensus_decode(reader)?
         } else {
-            // our prefix is the number of inputs
-            let mut inputs = Vec::with_capacity(prefix.to_usize());
+            // our prefix is the number of inputs. We do not pre-allocate
+            // for it, since it comes from untrusted data and may be huge.
+            let mut inputs = Vec::new();
             for _ in 0..prefix.to_u64() {
                 inputs.push(TxIn::consensus_decode(reader)?);
             }
@@ -330,6 +553,11 @@ impl ConsensusDecode for Tx {
             for input in &mut inputs {
                 input.witness = Witness::consensus_decode(reader)?;
             }
+            // Otherwise the transaction would have two valid serializations,
+            // and the serialization we produce would not match the decoded one
+            if inputs.iter().all(|input| input.witness.is_empty()) {
+                return Err(ConsensusDataError::SuperfluousWitnessFlag.into());
+            }
         }
         let lock_time = LockTime::consensus_decode(reader)?;
 
@@ -564,20 +792,20 @@ impl ConsensusDecode for Witness {
 impl ConsensusEncode for InternalPk {
     fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
         writer.write_all(&self.to_byte_array())?;
-        Ok(32)
+        Ok(InternalPk::LEN)
     }
 }
 
 impl ConsensusEncode for TapBranchHash {
     fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
         writer.write_all(&self.to_byte_array())?;
-        Ok(32)
+        Ok(TapNodeHash::LEN)
     }
 }
 
 impl ConsensusDecode for TapBranchHash {
     fn consensus_decode(reader: &mut impl Read) -> Result<Self, ConsensusDecodeError> {
-        let mut buf = [0u8; 32];
+        let mut buf = [0u8; TapNodeHash::LEN];
         reader.read_exact(&mut buf)?;
         Ok(TapBranchHash::from_byte_array(buf))
     }
@@ -585,7 +813,7 @@ impl ConsensusDecode for TapBranchHash {
 
 impl ConsensusDecode for InternalPk {
     fn consensus_decode(reader: &mut impl Read) -> Result<Self, ConsensusDecodeError> {
-        let mut buf = [0u8; 32];
+        let mut buf = [0u8; InternalPk::LEN];
         reader.read_exact(&mut buf)?;
         InternalPk::from_byte_array(buf)
             .map_err(|_| ConsensusDataError::InvalidXonlyPubkey(buf.into()).into())
@@ -597,7 +825,7 @@ impl ConsensusEncode for ControlBlock {
         let mut counter = 1;
 
         let first_byte =
-            self.leaf_version.to_consensus_u8() & self.output_key_parity.to_consensus_u8();
+            self.leaf_version.to_consensus_u8() | self.output_key_parity.to_consensus_u8();
         first_byte.consensus_encode(writer)?;
 
         counter += self.internal_pk.consensus_encode(writer)?;
@@ -619,13 +847,16 @@ impl ConsensusDecode for ControlBlock {
 
         let mut buf = vec![];
         reader.read_to_end(&mut buf)?;
-        let mut iter = buf.chunks_exact(32);
-        let merkle_branch = iter.by_ref().map(TapBranchHash::from_slice_unsafe);
-        let merkle_branch = TapMerklePath::try_from_iter(merkle_branch)
-            .map_err(|_| ConsensusDataError::LongTapMerklePath)?;
-        if !iter.remainder().is_empty() {
+        if buf.len() % TapNodeHash::LEN != 0 {
             return Err(ConsensusDataError::InvalidTapMerklePath.into());
         }
+        let merkle_branch = buf
+            .chunks_exact(TapNodeHash::LEN)
+            .map(TapBranchHash::from_slice)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ConsensusDataError::InvalidTapMerklePath)?;
+        let merkle_branch = TapMerklePath::try_from_iter(merkle_branch)
+            .map_err(|_| ConsensusDataError::LongTapMerklePath)?;
 
         Ok(ControlBlock {
             leaf_version,
@@ -636,6 +867,46 @@ impl ConsensusDecode for ControlBlock {
     }
 }
 
+impl ConsensusEncode for TapTree {
+    fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
+        let mut counter = 0;
+        for leaf in self {
+            counter += leaf.depth.consensus_encode(writer)?;
+            counter += leaf.script.version.to_consensus_u8().consensus_encode(writer)?;
+            counter += leaf.script.script.consensus_encode(writer)?;
+        }
+        Ok(counter)
+    }
+}
+
+/// Decodes the taproot script tree in the format of PSBT `PSBT_IN_TAP_TREE`
+/// and `PSBT_OUT_TAP_TREE` field values, consuming all the data from the
+/// reader.
+impl ConsensusDecode for TapTree {
+    fn consensus_decode(reader: &mut impl Read) -> Result<Self, ConsensusDecodeError> {
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf)?;
+        let len = buf.len() as u64;
+        let mut cursor = Cursor::new(buf);
+        let mut leaves = vec![];
+        while cursor.position() < len {
+            let depth = u8::consensus_decode(&mut cursor)?;
+            let version = LeafVer::from_consensus_u8(u8::consensus_decode(&mut cursor)?)?;
+            let script = ScriptBytes::consensus_decode(&mut cursor)?;
+            leaves.push(TapTreeLeaf {
+                depth,
+                script: LeafScript::new(version, script),
+            });
+        }
+        TapTree::from_leaves(leaves).map_err(|err| match err {
+            TapTreeError::TooDeep(_) => ConsensusDataError::LongTapMerklePath.into(),
+            TapTreeError::Empty | TapTreeError::Incomplete => {
+                ConsensusDataError::InvalidTapMerklePath.into()
+            }
+        })
+    }
+}
+
 impl ConsensusEncode for Sats {
     fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
         self.0.consensus_encode(writer)
@@ -851,7 +1122,52 @@ impl ConsensusDecode for [u8; 32] {
 
 #[cfg(test)]
 mod tests {
+    use std::fmt::Debug;
+
     use super::*;
+    use crate::opcodes::OP_PUSHNUM_1;
+    use crate::{Bip340Sig, IntoTapHash, SighashType};
+
+    #[test]
+    fn byte_str_building() {
+        let mut data = ByteStr::with_capacity(4);
+        assert!(data.is_empty());
+        data.push(0x50).unwrap();
+        data.extend_from_slice(&[1, 2, 3]).unwrap();
+        assert_eq!(&data[..], &[0x50, 1, 2, 3]);
+        assert_eq!(serialize(&data), vec![4, 0x50, 1, 2, 3]);
+        assert_eq!(ByteStr::try_from(vec![0x50, 1, 2, 3]).unwrap(), data);
+    }
+
+    #[test]
+    fn utxo_set() {
+        let utxos = [
+            (
+                Outpoint::new(Txid::from([1u8; 32]), 0u32),
+                TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64),
+            ),
+            (
+                Outpoint::new(Txid::from([2u8; 32]), 7u32),
+                TxOut::new(ScriptPubkey::op_return(&[2u8; 32]), 0u64),
+            ),
+        ];
+        let data = encode_utxo_set(utxos.iter().map(|(outpoint, txout)| (outpoint, txout)));
+        assert_eq!(data[0], 2);
+        let decoded =
+            decode_utxo_set(data.as_slice()).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(decoded, utxos);
+
+        assert_eq!(encode_utxo_set(Vec::<(Outpoint, TxOut)>::new()), vec![0]);
+        assert_eq!(decode_utxo_set([0u8].as_slice()).unwrap().count(), 0);
+        assert!(decode_utxo_set([0u8; 0].as_slice()).is_err());
+
+        // Truncated data: the first entry is read, the second fails and stops
+        // the iteration
+        let mut iter = decode_utxo_set(&data[..data.len() - 1]).unwrap();
+        assert_eq!(iter.next(), Some(Ok(utxos[0].clone())));
+        assert!(matches!(iter.next(), Some(Err(ConsensusDecodeError::Io(_)))));
+        assert_eq!(iter.next(), None);
+    }
 
     fn serialize(t: &impl ConsensusEncode) -> Vec<u8> {
         let mut vec = Vec::new();
@@ -869,10 +1185,65 @@ mod tests {
         let mut cursor = Cursor::new(d.as_ref());
         T::consensus_decode(&mut cursor).map_err(|err| match err {
             ConsensusDecodeError::Data(e) => e,
-            ConsensusDecodeError::Io(_) => unreachable!(),
+            err => panic!("unexpected I/O error: {err}"),
         })
     }
 
+    #[test]
+    fn io_error_offset() {
+        let err = deserialize::<u64>([0u8; 5]).unwrap_err();
+        assert!(matches!(err, ConsensusDecodeError::IoAt { offset: 0, .. }));
+
+        let mut data = vec![0u8; 8];
+        data.push(25);
+        data.extend([0x76, 0xa9, 0x14]);
+        let err = deserialize::<TxOut>(&data).unwrap_err();
+        assert!(matches!(err, ConsensusDecodeError::IoAt { offset: 12, .. }));
+
+        let tx = Tx::consensus_deserialize_hex(
+            "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff\
+             00ffffffff0100000000000000000000000000",
+        )
+        .unwrap();
+        let data = tx.consensus_serialize();
+        let err = Tx::consensus_deserialize(&data[..data.len() - 2]).unwrap_err();
+        assert!(
+            matches!(err, ConsensusDecodeError::IoAt { offset, .. } if offset == data.len() - 4)
+        );
+    }
+
+    #[test]
+    fn tap_tree() {
+        let leaf1 = LeafScript::with_bytes(LeafVer::TapScript, vec![0x51]).unwrap();
+        let leaf2 = LeafScript::with_bytes(LeafVer::TapScript, vec![0x52]).unwrap();
+        let data = [0x01, 0xc0, 0x01, 0x51, 0x01, 0xc0, 0x01, 0x52];
+
+        let tree = deserialize::<TapTree>(data).unwrap();
+        assert_eq!(tree.leaves().map(|leaf| leaf.depth).collect::<Vec<_>>(), vec![1, 1]);
+        assert_eq!(
+            tree.merkle_root(),
+            TapBranchHash::with_nodes(
+                leaf1.tap_leaf_hash().into_tap_hash(),
+                leaf2.tap_leaf_hash().into_tap_hash()
+            )
+            .into_tap_hash()
+        );
+        assert_eq!(serialize(&tree), data);
+
+        assert_eq!(
+            deserialize_partial::<TapTree>([0x01, 0xc0, 0x01, 0x51]),
+            Err(ConsensusDataError::InvalidTapMerklePath)
+        );
+        assert_eq!(
+            deserialize_partial::<TapTree>([0x00, 0xc0, 0x01, 0x51, 0x00, 0xc0, 0x01, 0x52]),
+            Err(ConsensusDataError::InvalidTapMerklePath)
+        );
+        assert_eq!(
+            deserialize_partial::<TapTree>([0x81, 0xc0, 0x01, 0x51]),
+            Err(ConsensusDataError::LongTapMerklePath)
+        );
+    }
+
     #[test]
     fn serialize_int_test() {
         // u8
@@ -1052,4 +1423,274 @@ mod tests {
         let failure64: Result<u64, _> = deserialize([1u8, 2, 3, 4, 5, 6, 7]);
         assert!(failure64.is_err());
     }
+
+    #[test]
+    fn unsupported_segwit_flag() {
+        // version 2, segwit marker, flag 0x02, followed by what would be a
+        // single input
+        let mut data = vec![0x02, 0x00, 0x00, 0x00, 0x00, 0x02, 0x01];
+        data.extend([0xAB; 41]);
+        let mut cursor = Cursor::new(data);
+        assert_eq!(
+            Tx::consensus_decode(&mut cursor).unwrap_err(),
+            ConsensusDecodeError::Data(ConsensusDataError::UnsupportedSegwitFlag {
+                flag: 0x02,
+                offset: 5
+            })
+        );
+        assert_eq!(cursor.position(), 6);
+    }
+
+    #[test]
+    fn decode_exact() {
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_input(TxIn {
+            prev_output: Outpoint::new(Txid::coinbase(), 0u32),
+            sig_script: SigScript::from_unsafe(vec![0x51]),
+            sequence: SeqNo::FINAL,
+            witness: none!(),
+        })
+        .unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64)).unwrap();
+        let data = tx.consensus_serialize();
+
+        assert_eq!(Tx::consensus_decode_exact(&mut data.as_slice()), Ok(tx.clone()));
+
+        let mut extended = data.clone();
+        extended.push(0x00);
+        assert_eq!(
+            Tx::consensus_decode_exact(&mut extended.as_slice()),
+            Err(ConsensusDecodeError::Data(ConsensusDataError::DataNotConsumed))
+        );
+        // Non-exact decoding leaves the trailing data in the reader
+        let mut reader = extended.as_slice();
+        assert_eq!(Tx::consensus_decode(&mut reader), Ok(tx));
+        assert_eq!(reader, &[0x00]);
+
+        assert!(matches!(
+            Tx::consensus_decode_exact(&mut &data[..data.len() - 1]),
+            Err(ConsensusDecodeError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn decode_bounded() {
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_input(TxIn {
+            prev_output: Outpoint::new(Txid::coinbase(), 0u32),
+            sig_script: SigScript::from_unsafe(vec![0x51]),
+            sequence: SeqNo::FINAL,
+            witness: none!(),
+        })
+        .unwrap();
+        let data = tx.consensus_serialize();
+
+        assert_eq!(Tx::consensus_decode_bounded(&mut data.as_slice(), data.len()), Ok(tx));
+        assert_eq!(
+            Tx::consensus_decode_bounded(&mut data.as_slice(), data.len() - 1),
+            Err(ConsensusDataError::DataTooLarge {
+                max: data.len() - 1
+            }
+            .into())
+        );
+
+        // Declared length of ~2GB must not be read from an endless reader
+        let mut reader = [0xFE, 0xFF, 0xFF, 0xFF, 0x7F].as_slice().chain(io::repeat(0xAA));
+        assert_eq!(
+            ByteStr::consensus_decode_bounded(&mut reader, 1000),
+            Err(ConsensusDataError::DataTooLarge { max: 1000 }.into())
+        );
+
+        // Declared number of inputs must not result in a huge pre-allocation
+        let data = [0x02, 0x00, 0x00, 0x00, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert!(matches!(
+            Tx::consensus_deserialize(data),
+            Err(ConsensusDecodeError::IoAt { offset: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn segwit_flag_consistency() {
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_input(TxIn {
+            prev_output: Outpoint::new(Txid::coinbase(), 0u32),
+            sig_script: none!(),
+            sequence: SeqNo::FINAL,
+            witness: Witness::from_consensus_stack([vec![0xAA; 72]]),
+        })
+        .unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64)).unwrap();
+        let segwit = tx.consensus_serialize();
+        assert_eq!(&segwit[4..6], &[0x00, 0x01]);
+        let decoded = Tx::consensus_deserialize(&segwit).unwrap();
+        assert!(decoded.is_segwit());
+        assert_eq!(decoded, tx);
+
+        // Segwit marker and flag present, but the only witness is empty
+        let (base, _) = tx.serialize_parts();
+        let mut malleated = base[..4].to_vec();
+        malleated.extend([0x00, 0x01]);
+        malleated.extend(&base[4..base.len() - 4]);
+        malleated.push(0x00);
+        malleated.extend(&base[base.len() - 4..]);
+        assert_eq!(
+            Tx::consensus_deserialize(&malleated),
+            Err(ConsensusDecodeError::Data(ConsensusDataError::SuperfluousWitnessFlag))
+        );
+
+        // Non-segwit serialization never produces a segwit transaction
+        let decoded = Tx::consensus_deserialize(&base).unwrap();
+        assert!(!decoded.is_segwit());
+        assert_eq!(decoded, tx.to_unsigned_tx());
+        assert_eq!(decoded.consensus_serialize(), base);
+    }
+
+    #[test]
+    fn genesis_block() {
+        let hex = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b\
+                   12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c01010000\
+                   00010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff\
+                   001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e2062\
+                   72696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01\
+                   000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f\
+                   4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+        let block = Block::consensus_deserialize_hex(hex).unwrap();
+        assert_eq!(
+            block.header.block_hash().to_string(),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(
+            block.transactions[0].txid().to_string(),
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+        );
+        assert_eq!(block.merkle_root(), block.header.merkle_root);
+        assert_eq!(block.consensus_serialize_hex(), hex);
+        assert_eq!(block.consensus_serialize().len(), 285);
+    }
+
+    #[test]
+    fn control_block_odd_parity() {
+        // BIP341 wallet test vector for a single-leaf script tree with odd
+        // output key parity
+        let internal_pk = InternalPk::from_byte_array(
+            <[u8; 32]>::from_hex(
+                "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let cb = ControlBlock::with(LeafVer::TapScript, internal_pk, Parity::Odd, none!());
+        assert_eq!(
+            cb.consensus_serialize_hex(),
+            "c1187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27"
+        );
+        assert_eq!(ControlBlock::consensus_deserialize(cb.consensus_serialize()).unwrap(), cb);
+    }
+
+    #[test]
+    fn control_block_merkle_path_len() {
+        let mut data = vec![0xC0];
+        data.extend(InternalPk::NUMS);
+        data.extend([0x11; 32]);
+        let cb = ControlBlock::consensus_deserialize(&data).unwrap();
+        assert_eq!(cb.merkle_branch.len(), 1);
+
+        data.push(0x22);
+        assert_eq!(
+            ControlBlock::consensus_deserialize(&data),
+            Err(ConsensusDecodeError::Data(ConsensusDataError::InvalidTapMerklePath))
+        );
+
+        let mut data = vec![0xC0];
+        data.extend(InternalPk::NUMS);
+        data.extend([0x11; 32 * 129]);
+        assert_eq!(
+            ControlBlock::consensus_deserialize(&data),
+            Err(ConsensusDecodeError::Data(ConsensusDataError::LongTapMerklePath))
+        );
+    }
+
+    #[test]
+    fn hash_from_slice() {
+        fn check<T: ByteArray<32> + Eq + Debug>() {
+            assert_eq!(T::from_slice([0xA5; 32]).unwrap(), T::from_byte_array([0xA5; 32]));
+            assert!(T::from_slice([0xA5; 31]).is_err());
+            assert!(T::from_slice([0xA5; 33]).is_err());
+            assert!(T::from_slice([0u8; 0]).is_err());
+        }
+        check::<TapBranchHash>();
+        check::<TapNodeHash>();
+        check::<TapLeafHash>();
+        check::<BlockHash>();
+        check::<BlockMerkleRoot>();
+        check::<Txid>();
+    }
+
+    #[test]
+    fn control_block_roundtrip() {
+        let cb = ControlBlock::with(
+            LeafVer::TapScript,
+            InternalPk::nums(),
+            Parity::Odd,
+            TapMerklePath::try_from(vec![TapBranchHash::from([0x11; 32])]).unwrap(),
+        );
+        let data = cb.consensus_serialize();
+        assert_eq!(data[0], 0xC1);
+        assert_eq!(ControlBlock::consensus_deserialize(&data).unwrap(), cb);
+
+        let script = LeafScript::with_bytes(LeafVer::TapScript, vec![OP_PUSHNUM_1]).unwrap();
+        let annex = Annex::try_from(vec![TAPROOT_ANNEX_PREFIX, 0x01]).unwrap();
+        let witness = Witness::for_tapscript_spend(vec![vec![0xAA]], &script, &cb);
+        assert_eq!(witness.elements().collect::<Vec<_>>(), vec![
+            &[0xAA][..],
+            &[OP_PUSHNUM_1][..],
+            &data[..]
+        ]);
+        let witness = witness.with_annex(&annex);
+        assert_eq!(witness.len(), 4);
+        assert_eq!(witness.elements().last(), Some(&[TAPROOT_ANNEX_PREFIX, 0x01][..]));
+    }
+
+    #[test]
+    fn taproot_key_spend_witness() {
+        let sig = Bip340Sig::from_bytes(&[0x11; 64]).unwrap();
+        let witness = Witness::for_taproot_key_spend(&sig, None);
+        assert_eq!(witness.elements().collect::<Vec<_>>(), vec![&[0x11; 64][..]]);
+
+        let mut sig_bytes = vec![0x11; 64];
+        sig_bytes.push(SighashType::all().into_consensus_u8());
+        let sig = Bip340Sig::from_bytes(&sig_bytes).unwrap();
+        let annex = Annex::try_from(vec![TAPROOT_ANNEX_PREFIX, 0x01]).unwrap();
+        let witness = Witness::for_taproot_key_spend(&sig, Some(annex));
+        assert_eq!(witness.elements().collect::<Vec<_>>(), vec![
+            &sig_bytes[..],
+            &[TAPROOT_ANNEX_PREFIX, 0x01][..]
+        ]);
+        assert_eq!(Witness::consensus_deserialize(witness.consensus_serialize()).unwrap(), witness);
+    }
+
+    #[test]
+    fn empty_script_pubkey() {
+        let spk = ScriptPubkey::new();
+        assert!(spk.is_empty());
+        assert!(!spk.is_p2pkh());
+        assert!(!spk.is_p2sh());
+        assert!(!spk.is_p2wpkh());
+        assert!(!spk.is_p2wsh());
+        assert!(!spk.is_p2tr());
+        assert!(!spk.is_op_return());
+        assert!(!spk.is_witness_program());
+        assert_eq!(spk.witness_version(), None);
+        assert_eq!(spk.witness_program(), None);
+
+        let ser = spk.consensus_serialize();
+        assert_eq!(ser, vec![0x00]);
+        assert_eq!(ScriptPubkey::consensus_deserialize(&ser).unwrap(), spk);
+
+        let txout = TxOut::new(spk, Sats::from_sats(1000u64));
+        let ser = txout.consensus_serialize();
+        assert_eq!(ser.len(), 9);
+        assert_eq!(TxOut::consensus_deserialize(&ser).unwrap(), txout);
+    }
 }
@@ -19,6 +19,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io;
+
 use amplify::{Bytes20, Bytes32, Wrapper};
 use commit_verify::{DigestExt, Ripemd160, Sha256};
 
@@ -26,6 +28,49 @@ use crate::{
     CompressedPk, LegacyPk, RedeemScript, UncompressedPk, WitnessScript, LIB_NAME_BITCOIN,
 };
 
+/// Double SHA256 (`SHA256(SHA256(data))`) hash engine, used for transaction
+/// ids, block hashes, merkle trees and legacy and segwit v0 signature hashes.
+#[derive(Default)]
+pub struct Sha256d(Sha256);
+
+impl From<Sha256> for Sha256d {
+    /// Continues hashing with an engine holding the data already hashed once.
+    fn from(engine: Sha256) -> Self { Self(engine) }
+}
+
+impl io::Write for Sha256d {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input_raw(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+impl Sha256d {
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Computes double SHA256 hash of the data in a single call.
+    pub fn digest(data: impl AsRef<[u8]>) -> [u8; 32] {
+        let mut engine = Self::new();
+        engine.input_raw(data.as_ref());
+        engine.finish()
+    }
+
+    #[inline]
+    pub fn input_raw(&mut self, data: &[u8]) { self.0.input_raw(data); }
+
+    /// Completes the hashing, applying the second round of SHA256.
+    pub fn finish(self) -> [u8; 32] {
+        let mut double = Sha256::default();
+        double.input_raw(&self.0.finish());
+        double.finish()
+    }
+}
+
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[wrapper(Index, RangeOps, AsSlice, BorrowSlice, Hex, Display, FromStr)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -145,3 +190,33 @@ impl From<&WitnessScript> for WScriptHash {
         Self(engine2.finish().into())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use amplify::hex::ToHex;
+
+    use super::*;
+
+    #[test]
+    fn sha256d() {
+        assert_eq!(
+            Sha256d::digest([]).to_hex(),
+            "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456"
+        );
+        assert_eq!(
+            Sha256d::digest(b"abc").to_hex(),
+            "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358"
+        );
+
+        let mut engine = Sha256d::new();
+        engine.write_all(b"a").unwrap();
+        engine.input_raw(b"bc");
+        assert_eq!(engine.finish(), Sha256d::digest(b"abc"));
+
+        let mut single = Sha256::default();
+        single.input_raw(b"abc");
+        assert_eq!(Sha256d::from(single).finish(), Sha256d::digest(b"abc"));
+    }
+}
@@ -0,0 +1,716 @@
+// Bitcoin protocol consensus library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integration point for script interpreters verifying transaction inputs, and
+//! a minimal interpreter for standard output types.
+
+use std::borrow::Borrow;
+use std::error::Error;
+
+use secp256k1::SECP256K1;
+
+use crate::opcodes::*;
+use crate::{
+    Annex, Bip340Sig, CompressedPk, ConsensusDecode, ConsensusDecodeError, ControlBlock,
+    Instruction, Instructions, IntoTapHash, LeafScript, LeafVer, LegacySig, OutputPk,
+    PrevoutMismatch, RedeemScript, ScriptBytes, ScriptCode, SigError, SigScript, SighashCache,
+    SighashError, TapBranchHash, TapLeafHash, Tx, TxOut, Txid, WPubkeyHash, WScriptHash, Witness,
+    WitnessScript, WitnessVer, XOnlyPk, TAPROOT_ANNEX_PREFIX,
+};
+
+/// Errors verifying transaction input.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum VerifyError<E: Error> {
+    /// invalid input index {index} in {txid} which has only {inputs} inputs.
+    InvalidInputIndex {
+        txid: Txid,
+        index: usize,
+        inputs: usize,
+    },
+
+    #[from]
+    #[display(inner)]
+    Prevouts(PrevoutMismatch),
+
+    /// script verification failed: {0}
+    Script(E),
+}
+
+/// Data of a transaction input provided to the [`ScriptInterpreter`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ScriptInput<'tx> {
+    /// Index of the input in the transaction.
+    pub index: usize,
+    /// Input signature script.
+    pub sig_script: &'tx SigScript,
+    /// Input witness.
+    pub witness: &'tx Witness,
+    /// Output spent by the input, providing script pubkey and the amount.
+    pub prevout: &'tx TxOut,
+}
+
+/// Script interpreter, which can be plugged in to verify transaction inputs
+/// using [`Tx::verify_input`].
+///
+/// The interpreter receives already prepared input data and the signature hash
+/// cache for the transaction, which should be used to compute the messages for
+/// signature verification.
+pub trait ScriptInterpreter {
+    /// Error returned by the interpreter on a script verification failure.
+    type Error: Error;
+
+    /// Verifies that the input satisfies the script pubkey of the spent
+    /// output.
+    fn verify_input<Prevout: Borrow<TxOut>, Transaction: Borrow<Tx>>(
+        &mut self,
+        input: ScriptInput,
+        sighash_cache: &mut SighashCache<Prevout, Transaction>,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Errors verifying inputs with [`StandardInterpreter`].
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum StandardScriptError {
+    /// spent output is not of a supported type (P2WPKH, P2WSH or P2TR).
+    UnsupportedOutput,
+
+    /// input spending a native segwit output has non-empty signature script.
+    NonEmptySigScript,
+
+    /// invalid number of witness stack elements ({0}) for the spent output
+    /// type.
+    WitnessStackLen(usize),
+
+    /// public key in the witness doesn't match the spent P2WPKH output.
+    PubkeyMismatch,
+
+    /// witness script doesn't match the spent P2WSH output.
+    WitnessScriptMismatch,
+
+    /// witness script is neither a single-key nor a multisig script.
+    UnsupportedWitnessScript,
+
+    /// taproot script path spends are not supported.
+    TapscriptSpend,
+
+    /// public key in the witness is not a valid compressed public key.
+    InvalidPubkey,
+
+    /// the first (dummy) element of a multisig witness is not empty.
+    NonEmptyMultisigDummy,
+
+    /// signature doesn't match the public key and the transaction.
+    InvalidSignature,
+
+    #[from]
+    #[display(inner)]
+    Sig(SigError),
+
+    #[from]
+    #[display(inner)]
+    Sighash(SighashError),
+}
+
+/// Minimal script interpreter verifying signatures in spends of the standard
+/// output types: P2WPKH, P2TR key path and P2WSH with a single-key
+/// (`<pk> OP_CHECKSIG`) or a multisig (`OP_k <pk>... OP_n OP_CHECKMULTISIG`)
+/// witness script.
+///
+/// The interpreter matches inputs against the templates and does not execute
+/// any other scripts; all other spends are rejected.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct StandardInterpreter;
+
+impl ScriptInterpreter for StandardInterpreter {
+    type Error = StandardScriptError;
+
+    fn verify_input<Prevout: Borrow<TxOut>, Transaction: Borrow<Tx>>(
+        &mut self,
+        input: ScriptInput,
+        sighash_cache: &mut SighashCache<Prevout, Transaction>,
+    ) -> Result<(), Self::Error> {
+        let spk = &input.prevout.script_pubkey;
+        let (Some(ver), Some(program)) = (spk.witness_version(), spk.witness_program()) else {
+            return Err(StandardScriptError::UnsupportedOutput);
+        };
+        if !input.sig_script.is_empty() {
+            return Err(StandardScriptError::NonEmptySigScript);
+        }
+        let stack = input.witness.elements().collect::<Vec<_>>();
+        match (ver, program.len()) {
+            (WitnessVer::V0, 20) => verify_p2wpkh(input, program, &stack, sighash_cache),
+            (WitnessVer::V0, 32) => verify_p2wsh(input, program, &stack, sighash_cache),
+            (WitnessVer::V1, 32) => verify_p2tr_key(input, program, &stack, sighash_cache),
+            _ => Err(StandardScriptError::UnsupportedOutput),
+        }
+    }
+}
+
+fn verify_p2wpkh<Prevout: Borrow<TxOut>, Transaction: Borrow<Tx>>(
+    input: ScriptInput,
+    program: &[u8],
+    stack: &[&[u8]],
+    sighash_cache: &mut SighashCache<Prevout, Transaction>,
+) -> Result<(), StandardScriptError> {
+    let [sig, pk] = stack else {
+        return Err(StandardScriptError::WitnessStackLen(stack.len()));
+    };
+    let pk = parse_pubkey(pk)?;
+    if <[u8; 20]>::from(WPubkeyHash::from(pk)) != program {
+        return Err(StandardScriptError::PubkeyMismatch);
+    }
+    let script_code = ScriptCode::with_p2wpkh(&input.prevout.script_pubkey);
+    verify_ecdsa(input, &script_code, sig, pk, sighash_cache)
+}
+
+fn verify_p2wsh<Prevout: Borrow<TxOut>, Transaction: Borrow<Tx>>(
+    input: ScriptInput,
+    program: &[u8],
+    stack: &[&[u8]],
+    sighash_cache: &mut SighashCache<Prevout, Transaction>,
+) -> Result<(), StandardScriptError> {
+    let Some((script, stack)) = stack.split_last() else {
+        return Err(StandardScriptError::WitnessStackLen(0));
+    };
+    let witness_script = WitnessScript::from_unsafe(script.to_vec());
+    if <[u8; 32]>::from(WScriptHash::from(&witness_script)) != program {
+        return Err(StandardScriptError::WitnessScriptMismatch);
+    }
+    let script_code = ScriptCode::with_p2wsh(&witness_script);
+
+    if let [0x21, pk @ .., OP_CHECKSIG] = *script {
+        let [sig] = stack else {
+            return Err(StandardScriptError::WitnessStackLen(stack.len() + 1));
+        };
+        let pk = parse_pubkey(pk)?;
+        return verify_ecdsa(input, &script_code, sig, pk, sighash_cache);
+    }
+
+    let (k, pubkeys) =
+        parse_multisig(script).ok_or(StandardScriptError::UnsupportedWitnessScript)?;
+    let [dummy, sigs @ ..] = stack else {
+        return Err(StandardScriptError::WitnessStackLen(stack.len() + 1));
+    };
+    if sigs.len() != k {
+        return Err(StandardScriptError::WitnessStackLen(stack.len() + 1));
+    }
+    if !dummy.is_empty() {
+        return Err(StandardScriptError::NonEmptyMultisigDummy);
+    }
+    // Signatures must follow the order of the public keys, as in
+    // `OP_CHECKMULTISIG` execution
+    let mut pubkeys = pubkeys.into_iter();
+    for sig in sigs {
+        let sig = LegacySig::from_bytes(sig)?;
+        let sighash = sighash_cache.segwit_sighash(
+            input.index,
+            &script_code,
+            input.prevout.value,
+            sig.sighash_type,
+        )?;
+        let msg = secp256k1::Message::from(sighash);
+        let mut ecdsa_sig = sig.sig;
+        ecdsa_sig.normalize_s();
+        if !pubkeys.any(|pk| SECP256K1.verify_ecdsa(&msg, &ecdsa_sig, &pk).is_ok()) {
+            return Err(StandardScriptError::InvalidSignature);
+        }
+    }
+    Ok(())
+}
+
+fn verify_p2tr_key<Prevout: Borrow<TxOut>, Transaction: Borrow<Tx>>(
+    input: ScriptInput,
+    program: &[u8],
+    mut stack: &[&[u8]],
+    sighash_cache: &mut SighashCache<Prevout, Transaction>,
+) -> Result<(), StandardScriptError> {
+    let mut annex = None;
+    if let [rest @ .., last] = stack {
+        if !rest.is_empty() && last.first() == Some(&TAPROOT_ANNEX_PREFIX) {
+            annex = Some(Annex::new(last.to_vec()).expect("annex prefix is checked"));
+            stack = rest;
+        }
+    }
+    let sig = match stack {
+        [sig] => Bip340Sig::from_bytes(sig)?,
+        [] => return Err(StandardScriptError::WitnessStackLen(0)),
+        _ => return Err(StandardScriptError::TapscriptSpend),
+    };
+    let output_pk =
+        OutputPk::from_bytes(program).map_err(|_| StandardScriptError::InvalidPubkey)?;
+    let sighash = sighash_cache.tap_sighash_custom(input.index, annex, None, sig.sighash_type)?;
+    SECP256K1
+        .verify_schnorr(&sig.sig, &<[u8; 32]>::from(sighash), &output_pk.to_xonly_pk())
+        .map_err(|_| StandardScriptError::InvalidSignature)
+}
+
+/// Errors verifying taproot script path spends with
+/// [`verify_script_path_spend`].
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum TaprootSpendError {
+    /// spent output is not a P2TR output.
+    NotP2tr,
+
+    /// invalid number of witness stack elements ({0}) for a single-key
+    /// tapscript spend.
+    WitnessStackLen(usize),
+
+    /// invalid control block: {0}
+    ControlBlock(ConsensusDecodeError),
+
+    /// leaf script and control block don't commit to the output key of the
+    /// spent output.
+    OutputKeyMismatch,
+
+    /// leaf script is not a single-key (`<pk> OP_CHECKSIG`) tapscript.
+    UnsupportedLeafScript,
+
+    /// public key in the leaf script is not a valid BIP340 public key.
+    InvalidPubkey,
+
+    /// signature doesn't match the public key and the transaction.
+    InvalidSignature,
+
+    #[from]
+    #[display(inner)]
+    Prevouts(PrevoutMismatch),
+
+    #[from]
+    #[display(inner)]
+    Sig(SigError),
+
+    #[from]
+    #[display(inner)]
+    Sighash(SighashError),
+}
+
+/// Verifies taproot script path spend of the transaction input with the given
+/// index by the `witness`, which may be not yet a part of the transaction.
+///
+/// The witness must contain the leaf script and the control block, which are
+/// checked to commit to the output key of the spent output, and a BIP340
+/// signature satisfying the leaf script. Only single-key (`<pk> OP_CHECKSIG`)
+/// leaf scripts are supported, since other scripts require a full script
+/// interpreter.
+///
+/// `prevouts` must contain outputs spent by all transaction inputs in the
+/// order of the inputs, since taproot signature hashes commit to all of them.
+pub fn verify_script_path_spend(
+    prevouts: &[TxOut],
+    witness: &Witness,
+    tx: &Tx,
+    input_index: usize,
+) -> Result<(), TaprootSpendError> {
+    let mut sighash_cache = SighashCache::new(tx, prevouts.iter().collect())?;
+    let prevout = prevouts.get(input_index).ok_or_else(|| SighashError::InvalidInputIndex {
+        txid: tx.txid(),
+        index: input_index,
+        inputs: tx.inputs.len(),
+    })?;
+    let spk = &prevout.script_pubkey;
+    if !spk.is_p2tr() {
+        return Err(TaprootSpendError::NotP2tr);
+    }
+    let program = spk.witness_program().expect("P2TR is a witness program");
+
+    let elements = witness.elements().collect::<Vec<_>>();
+    let mut stack = elements.as_slice();
+    let mut annex = None;
+    if let [rest @ .., last] = stack {
+        if !rest.is_empty() && last.first() == Some(&TAPROOT_ANNEX_PREFIX) {
+            annex = Some(Annex::new(last.to_vec()).expect("annex prefix is checked"));
+            stack = rest;
+        }
+    }
+    let [sig, script, control_block] = stack else {
+        return Err(TaprootSpendError::WitnessStackLen(stack.len()));
+    };
+
+    let control_block = ControlBlock::consensus_deserialize(control_block)
+        .map_err(TaprootSpendError::ControlBlock)?;
+    let leaf_script =
+        LeafScript::new(control_block.leaf_version, ScriptBytes::from_unsafe(script.to_vec()));
+    let leaf_hash = TapLeafHash::with_leaf_script(&leaf_script);
+    let merkle_root =
+        control_block.merkle_branch.iter().fold(leaf_hash.into_tap_hash(), |node, sibling| {
+            TapBranchHash::with_nodes(node, sibling.into_tap_hash()).into_tap_hash()
+        });
+    let (output_pk, parity) = control_block.internal_pk.to_output_pk(Some(merkle_root));
+    if output_pk.to_byte_array() != program || parity != control_block.output_key_parity {
+        return Err(TaprootSpendError::OutputKeyMismatch);
+    }
+
+    let (LeafVer::TapScript, [0x20, pk @ .., OP_CHECKSIG]) = (leaf_script.version, *script) else {
+        return Err(TaprootSpendError::UnsupportedLeafScript);
+    };
+    let pk = <[u8; 32]>::try_from(pk).map_err(|_| TaprootSpendError::UnsupportedLeafScript)?;
+    let pk = XOnlyPk::from_byte_array(pk).map_err(|_| TaprootSpendError::InvalidPubkey)?;
+    let sig = Bip340Sig::from_bytes(sig)?;
+    let sighash = sighash_cache.tap_sighash_custom(
+        input_index,
+        annex,
+        Some((leaf_hash, 0xFFFFFFFF)),
+        sig.sighash_type,
+    )?;
+    SECP256K1
+        .verify_schnorr(&sig.sig, &<[u8; 32]>::from(sighash), &pk)
+        .map_err(|_| TaprootSpendError::InvalidSignature)
+}
+
+fn verify_ecdsa<Prevout: Borrow<TxOut>, Transaction: Borrow<Tx>>(
+    input: ScriptInput,
+    script_code: &ScriptCode,
+    sig: &[u8],
+    pk: CompressedPk,
+    sighash_cache: &mut SighashCache<Prevout, Transaction>,
+) -> Result<(), StandardScriptError> {
+    let sig = LegacySig::from_bytes(sig)?;
+    let sighash = sighash_cache.segwit_sighash(
+        input.index,
+        script_code,
+        input.prevout.value,
+        sig.sighash_type,
+    )?;
+    let mut ecdsa_sig = sig.sig;
+    ecdsa_sig.normalize_s();
+    SECP256K1
+        .verify_ecdsa(&sighash.into(), &ecdsa_sig, &pk)
+        .map_err(|_| StandardScriptError::InvalidSignature)
+}
+
+fn parse_pubkey(data: &[u8]) -> Result<CompressedPk, StandardScriptError> {
+    let data = <[u8; 33]>::try_from(data).map_err(|_| StandardScriptError::InvalidPubkey)?;
+    CompressedPk::from_byte_array(data).map_err(|_| StandardScriptError::InvalidPubkey)
+}
+
+/// Parses `OP_k <pk>... OP_n OP_CHECKMULTISIG` script, requiring it to be
+/// encoded exactly as produced by [`RedeemScript::multisig`].
+fn parse_multisig(script: &[u8]) -> Option<(usize, Vec<CompressedPk>)> {
+    fn small_num(instruction: Instruction) -> Option<usize> {
+        match instruction {
+            Instruction::Op(op @ OP_PUSHNUM_1..=OP_PUSHNUM_16) => {
+                Some((op - OP_PUSHNUM_1) as usize + 1)
+            }
+            Instruction::PushBytes { data: &[num], .. } => Some(num as usize),
+            _ => None,
+        }
+    }
+
+    let instructions = Instructions::new(script).collect::<Result<Vec<_>, _>>().ok()?;
+    let [k, keys @ .., n, Instruction::Op(OP_CHECKMULTISIG)] = instructions.as_slice() else {
+        return None;
+    };
+    let k = small_num(*k)?;
+    let pubkeys = keys
+        .iter()
+        .map(|instruction| parse_pubkey(instruction.push_bytes()?).ok())
+        .collect::<Option<Vec<_>>>()?;
+    if small_num(*n)? != pubkeys.len() {
+        return None;
+    }
+    let expected = RedeemScript::multisig(u8::try_from(k).ok()?, &pubkeys).ok()?;
+    (expected.as_slice() == script).then_some((k, pubkeys))
+}
+
+impl Tx {
+    /// Verifies transaction input with the given index using the provided
+    /// script interpreter.
+    ///
+    /// `prevouts` must contain outputs spent by all transaction inputs in the
+    /// order of the inputs, since taproot signature hashes commit to all of
+    /// them.
+    pub fn verify_input<I: ScriptInterpreter>(
+        &self,
+        index: usize,
+        prevouts: &[TxOut],
+        interpreter: &mut I,
+    ) -> Result<(), VerifyError<I::Error>> {
+        let txin = self.inputs.get(index).ok_or_else(|| VerifyError::InvalidInputIndex {
+            txid: self.txid(),
+            index,
+            inputs: self.inputs.len(),
+        })?;
+        let mut sighash_cache = SighashCache::new(self, prevouts.iter().collect())?;
+        let input = ScriptInput {
+            index,
+            sig_script: &txin.sig_script,
+            witness: &txin.witness,
+            prevout: &prevouts[index],
+        };
+        interpreter.verify_input(input, &mut sighash_cache).map_err(VerifyError::Script)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use secp256k1::{Keypair, PublicKey, SecretKey};
+
+    use super::*;
+    use crate::{
+        InternalKeypair, InternalPk, LockTime, Outpoint, Parity, ScriptPubkey, SeqNo, SighashType,
+        TapMerklePath, TxIn, TxVer,
+    };
+
+    fn spending_tx(witness: Witness) -> Tx {
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_input(TxIn {
+            prev_output: Outpoint::new(Txid::from([1u8; 32]), 0u32),
+            sig_script: none!(),
+            sequence: SeqNo::FINAL,
+            witness,
+        })
+        .unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::p2wpkh([2u8; 20]), 9000u64)).unwrap();
+        tx
+    }
+
+    fn keys(seed: u8) -> (SecretKey, CompressedPk) {
+        let sk = SecretKey::from_slice(&[seed; 32]).unwrap();
+        let pk = PublicKey::from_secret_key(SECP256K1, &sk);
+        (sk, CompressedPk::from_byte_array(pk.serialize()).unwrap())
+    }
+
+    fn ecdsa_sig(tx: &Tx, prevouts: &[TxOut], script_code: &ScriptCode, sk: &SecretKey) -> Vec<u8> {
+        let mut cache = SighashCache::new(tx, prevouts.iter().collect()).unwrap();
+        let sighash =
+            cache.segwit_sighash(0, script_code, prevouts[0].value, SighashType::all()).unwrap();
+        LegacySig::sighash_all(SECP256K1.sign_ecdsa(&sighash.into(), sk)).to_vec()
+    }
+
+    #[test]
+    fn standard_p2wpkh() {
+        let (sk, pk) = keys(0x11);
+        let prevouts = [TxOut::new(ScriptPubkey::p2wpkh(WPubkeyHash::from(pk)), 10000u64)];
+        let script_code = ScriptCode::with_p2wpkh(&prevouts[0].script_pubkey);
+        let sig = ecdsa_sig(&spending_tx(none!()), &prevouts, &script_code, &sk);
+
+        let witness = Witness::from_consensus_stack([sig.clone(), pk.to_byte_array().to_vec()]);
+        let tx = spending_tx(witness);
+        tx.verify_input(0, &prevouts, &mut StandardInterpreter).unwrap();
+
+        let (_, other_pk) = keys(0x22);
+        let witness = Witness::from_consensus_stack([sig, other_pk.to_byte_array().to_vec()]);
+        assert_eq!(
+            spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter),
+            Err(VerifyError::Script(StandardScriptError::PubkeyMismatch))
+        );
+
+        let (other_sk, _) = keys(0x22);
+        let sig = ecdsa_sig(&spending_tx(none!()), &prevouts, &script_code, &other_sk);
+        let witness = Witness::from_consensus_stack([sig, pk.to_byte_array().to_vec()]);
+        assert_eq!(
+            spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter),
+            Err(VerifyError::Script(StandardScriptError::InvalidSignature))
+        );
+    }
+
+    #[test]
+    fn standard_p2wsh_multisig() {
+        let (sk1, pk1) = keys(0x11);
+        let (sk2, pk2) = keys(0x22);
+        let redeem_script = RedeemScript::multisig(2, &[pk1, pk2]).unwrap();
+        let witness_script = WitnessScript::from_unsafe(redeem_script.to_vec());
+        let prevouts = [TxOut::new(witness_script.to_script_pubkey(), 10000u64)];
+        let script_code = ScriptCode::with_p2wsh(&witness_script);
+        let sig1 = ecdsa_sig(&spending_tx(none!()), &prevouts, &script_code, &sk1);
+        let sig2 = ecdsa_sig(&spending_tx(none!()), &prevouts, &script_code, &sk2);
+
+        let witness = Witness::from_consensus_stack([
+            vec![],
+            sig1.clone(),
+            sig2.clone(),
+            witness_script.to_vec(),
+        ]);
+        spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter).unwrap();
+
+        let witness =
+            Witness::from_consensus_stack([vec![], sig2, sig1.clone(), witness_script.to_vec()]);
+        assert_eq!(
+            spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter),
+            Err(VerifyError::Script(StandardScriptError::InvalidSignature))
+        );
+
+        let witness = Witness::from_consensus_stack([vec![], sig1, witness_script.to_vec()]);
+        assert_eq!(
+            spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter),
+            Err(VerifyError::Script(StandardScriptError::WitnessStackLen(3)))
+        );
+    }
+
+    #[test]
+    fn standard_p2tr_key() {
+        let keypair = Keypair::from_seckey_slice(SECP256K1, &[0x33; 32]).unwrap();
+        let (output_keypair, _) = InternalKeypair::from(keypair).to_output_keypair(None);
+        let output_pk =
+            OutputPk::from_byte_array(output_keypair.x_only_public_key().0.serialize()).unwrap();
+        let prevouts = [TxOut::new(ScriptPubkey::p2tr_tweaked(output_pk), 10000u64)];
+
+        let tx = spending_tx(none!());
+        let mut cache = SighashCache::new(&tx, prevouts.iter().collect()).unwrap();
+        let sighash = cache.tap_sighash_key(0, None).unwrap();
+        let sig = SECP256K1.sign_schnorr_no_aux_rand(&<[u8; 32]>::from(sighash), &output_keypair);
+        let sig = Bip340Sig::sighash_default(sig);
+
+        let witness = Witness::for_taproot_key_spend(&sig, None);
+        spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter).unwrap();
+
+        // Annex is committed to by the signature hash
+        let annex = Annex::new(vec![TAPROOT_ANNEX_PREFIX]).unwrap();
+        let witness = Witness::for_taproot_key_spend(&sig, Some(annex.clone()));
+        assert_eq!(
+            spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter),
+            Err(VerifyError::Script(StandardScriptError::InvalidSignature))
+        );
+        let sighash = cache.tap_sighash_custom(0, Some(annex.clone()), None, None).unwrap();
+        let annex_sig =
+            SECP256K1.sign_schnorr_no_aux_rand(&<[u8; 32]>::from(sighash), &output_keypair);
+        let annex_sig = Bip340Sig::sighash_default(annex_sig);
+        let witness = Witness::for_taproot_key_spend(&annex_sig, Some(annex));
+        spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter).unwrap();
+        let witness = Witness::for_taproot_key_spend(&annex_sig, None);
+        assert_eq!(
+            spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter),
+            Err(VerifyError::Script(StandardScriptError::InvalidSignature))
+        );
+
+        let witness = Witness::from_consensus_stack([sig.to_vec(), vec![OP_PUSHNUM_1]]);
+        assert_eq!(
+            spending_tx(witness).verify_input(0, &prevouts, &mut StandardInterpreter),
+            Err(VerifyError::Script(StandardScriptError::TapscriptSpend))
+        );
+    }
+
+    #[test]
+    fn script_path_spend() {
+        let keypair = Keypair::from_seckey_slice(SECP256K1, &[0x33; 32]).unwrap();
+        let mut script = vec![0x20];
+        script.extend(keypair.x_only_public_key().0.serialize());
+        script.push(OP_CHECKSIG);
+        let leaf_script = LeafScript::with_bytes(LeafVer::TapScript, script).unwrap();
+        let leaf_hash = leaf_script.tap_leaf_hash();
+        let sibling = TapBranchHash::from([7u8; 32]);
+        let merkle_root = TapBranchHash::with_nodes(leaf_hash.into(), sibling.into());
+
+        let internal_pk = InternalPk::nums();
+        let (output_pk, parity) = internal_pk.to_output_pk(Some(merkle_root.into()));
+        let prevouts = [TxOut::new(ScriptPubkey::p2tr_tweaked(output_pk), 10000u64)];
+        let merkle_branch = TapMerklePath::try_from(vec![sibling]).unwrap();
+        let control_block =
+            ControlBlock::with(LeafVer::TapScript, internal_pk, parity, merkle_branch);
+
+        let tx = spending_tx(none!());
+        let mut cache = SighashCache::new(&tx, prevouts.iter().collect()).unwrap();
+        let sighash = cache.tap_sighash_script(0, leaf_hash, None).unwrap();
+        let sig = SECP256K1.sign_schnorr_no_aux_rand(&<[u8; 32]>::from(sighash), &keypair);
+        let sig = Bip340Sig::sighash_default(sig);
+
+        let witness =
+            Witness::for_tapscript_spend(vec![sig.to_vec()], &leaf_script, &control_block);
+        verify_script_path_spend(&prevouts, &witness, &tx, 0).unwrap();
+        assert_eq!(
+            verify_script_path_spend(&prevouts, &witness, &tx, 1),
+            Err(TaprootSpendError::Sighash(SighashError::InvalidInputIndex {
+                txid: tx.txid(),
+                index: 1,
+                inputs: 1
+            }))
+        );
+
+        // Annex is committed to by the signature hash
+        let annex = Annex::new(vec![TAPROOT_ANNEX_PREFIX]).unwrap();
+        assert_eq!(
+            verify_script_path_spend(&prevouts, &witness.clone().with_annex(&annex), &tx, 0),
+            Err(TaprootSpendError::InvalidSignature)
+        );
+
+        let mut wrong_control_block = control_block.clone();
+        wrong_control_block.output_key_parity = parity ^ Parity::Odd;
+        let witness =
+            Witness::for_tapscript_spend(vec![sig.to_vec()], &leaf_script, &wrong_control_block);
+        assert_eq!(
+            verify_script_path_spend(&prevouts, &witness, &tx, 0),
+            Err(TaprootSpendError::OutputKeyMismatch)
+        );
+
+        let witness = Witness::for_tapscript_spend(vec![], &leaf_script, &control_block);
+        assert_eq!(
+            verify_script_path_spend(&prevouts, &witness, &tx, 0),
+            Err(TaprootSpendError::WitnessStackLen(2))
+        );
+    }
+
+    #[derive(Debug, Display, Error)]
+    #[display("unexpected script pubkey")]
+    struct StubError;
+
+    struct StubInterpreter(ScriptPubkey);
+
+    impl ScriptInterpreter for StubInterpreter {
+        type Error = StubError;
+
+        fn verify_input<Prevout: Borrow<TxOut>, Transaction: Borrow<Tx>>(
+            &mut self,
+            input: ScriptInput,
+            sighash_cache: &mut SighashCache<Prevout, Transaction>,
+        ) -> Result<(), Self::Error> {
+            sighash_cache.tap_sighash_key(input.index, None).unwrap();
+            if input.prevout.script_pubkey != self.0 {
+                return Err(StubError);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn verify_input() {
+        let spk = ScriptPubkey::p2wpkh([1u8; 20]);
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_input(TxIn {
+            prev_output: Outpoint::coinbase(),
+            sig_script: none!(),
+            sequence: SeqNo::FINAL,
+            witness: none!(),
+        })
+        .unwrap();
+        let prevouts = [TxOut::new(spk.clone(), 1000u64)];
+
+        let mut interpreter = StubInterpreter(spk);
+        assert!(tx.verify_input(0, &prevouts, &mut interpreter).is_ok());
+        assert!(matches!(
+            tx.verify_input(1, &prevouts, &mut interpreter),
+            Err(VerifyError::InvalidInputIndex {
+                index: 1,
+                inputs: 1,
+                ..
+            })
+        ));
+        assert!(matches!(tx.verify_input(0, &[], &mut interpreter), Err(VerifyError::Prevouts(_))));
+
+        let mut interpreter = StubInterpreter(ScriptPubkey::p2wpkh([2u8; 20]));
+        assert!(matches!(
+            tx.verify_input(0, &prevouts, &mut interpreter),
+            Err(VerifyError::Script(StubError))
+        ));
+    }
+}
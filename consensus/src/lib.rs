@@ -51,6 +51,8 @@ extern crate core;
 pub extern crate secp256k1;
 
 mod block;
+#[cfg(feature = "serde")]
+pub mod bitcoind;
 pub mod opcodes;
 mod script;
 mod pubkeys;
@@ -58,6 +60,7 @@ mod segwit;
 mod taproot;
 mod tx;
 mod hashtypes;
+pub mod interpreter;
 mod sigtypes;
 mod timelocks;
 mod util;
@@ -67,22 +70,36 @@ pub mod stl;
 mod coding;
 mod sigcache;
 
-pub use block::{BlockHash, BlockHeader, BlockMerkleRoot};
+pub use block::{verify_merkle_proof, Block, BlockHash, BlockHeader, BlockMerkleRoot};
 pub use coding::{
-    ByteStr, ConsensusDataError, ConsensusDecode, ConsensusDecodeError, ConsensusEncode, LenVarInt,
-    VarInt, VarIntArray, VarIntBytes,
+    decode_utxo_set, encode_utxo_set, ByteStr, ConsensusDataError, ConsensusDecode,
+    ConsensusDecodeError, ConsensusEncode, LenVarInt, VarInt, VarIntArray, VarIntBytes,
+};
+pub use hashtypes::{PubkeyHash, ScriptHash, Sha256d, WPubkeyHash, WScriptHash};
+pub use interpreter::{
+    verify_script_path_spend, ScriptInput, ScriptInterpreter, StandardInterpreter,
+    StandardScriptError, TaprootSpendError, VerifyError,
 };
-pub use hashtypes::{PubkeyHash, ScriptHash, WPubkeyHash, WScriptHash};
 pub use opcodes::OpCode;
 pub use pubkeys::{CompressedPk, InvalidPubkey, LegacyPk, PubkeyParseError, UncompressedPk};
-pub use script::{RedeemScript, ScriptBytes, ScriptPubkey, SigScript};
-pub use segwit::{SegwitError, Witness, WitnessProgram, WitnessScript, WitnessVer, Wtxid};
+pub use script::{
+    Instruction, Instructions, RedeemScript, ScriptBytes, ScriptError, ScriptParseError,
+    ScriptPubkey, ScriptRef, SigScript, MAX_MULTISIG_KEYS, MAX_OP_RETURN_DATA,
+};
+pub use segwit::{
+    SegwitError, ShWsh, Witness, WitnessCommitment, WitnessProgram, WitnessScript, WitnessVer,
+    Wtxid, WITNESS_COMMITMENT_PREFIX,
+};
 pub use sigcache::{PrevoutMismatch, SighashCache, SighashError};
-pub use sigtypes::{Bip340Sig, LegacySig, ScriptCode, SigError, Sighash, SighashFlag, SighashType};
+pub use sigtypes::{
+    Bip340Sig, LegacySig, ScriptCode, SigError, Sighash, SighashFlag, SighashType,
+    SighashTypeParseError,
+};
 pub use taproot::{
     Annex, AnnexError, ControlBlock, FutureLeafVer, InternalKeypair, InternalPk, IntoTapHash,
-    InvalidLeafVer, InvalidParityValue, LeafScript, LeafVer, OutputPk, Parity, TapBranchHash,
-    TapCode, TapLeafHash, TapMerklePath, TapNodeHash, TapScript, TapSighash, XOnlyPk,
+    InvalidLeafVer, InvalidParityValue, LeafScript, LeafStandardnessError, LeafVer, OutputPk,
+    Parity, TapBranchHash, TapCode, TapLeafHash, TapMerklePath, TapNode, TapNodeHash, TapScript,
+    TapSighash, TapTree, TapTreeError, TapTreeLeaf, XOnlyPk, MAX_STANDARD_TX_WEIGHT,
     MIDSTATE_TAPSIGHASH, TAPROOT_ANNEX_PREFIX, TAPROOT_LEAF_MASK, TAPROOT_LEAF_TAPSCRIPT,
 };
 pub use timelocks::{
@@ -90,9 +107,10 @@ pub use timelocks::{
     LOCKTIME_THRESHOLD, SEQ_NO_CSV_DISABLE_MASK, SEQ_NO_CSV_TYPE_MASK,
 };
 pub use tx::{
-    BlockDataParseError, Outpoint, OutpointParseError, Sats, Tx, TxIn, TxOut, TxVer, Txid, Vout,
+    BlockDataParseError, Outpoint, OutpointParseError, Sats, Tx, TxIn, TxIndexError, TxOut, TxVer,
+    Txid, TxidParseError, Vout,
 };
 pub use util::NonStandardValue;
-pub use weights::{VBytes, Weight, WeightUnits};
+pub use weights::{VBytes, Weight, WeightUnits, DUST_RELAY_FEE_RATE, TXIN_BASE_SIZE};
 
 pub const LIB_NAME_BITCOIN: &str = "Bitcoin";
@@ -71,6 +71,22 @@ impl CompressedPk {
     pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, InvalidPubkey<33>> {
         Ok(CompressedPk(PublicKey::from_slice(bytes.as_ref())?))
     }
+
+    /// Adds together this key and all of the `others` as elliptic curve
+    /// points.
+    ///
+    /// # Errors
+    ///
+    /// If the resulting sum is the point at infinity, i.e. some of the keys
+    /// cancel each other out.
+    pub fn combine(&self, others: &[CompressedPk]) -> Result<Self, secp256k1::Error> {
+        let keys = [&self.0].into_iter().chain(others.iter().map(|pk| &pk.0)).collect::<Vec<_>>();
+        PublicKey::combine_keys(&keys).map(Self)
+    }
+
+    /// Negates the key point.
+    #[must_use]
+    pub fn negate(&self) -> Self { Self(self.0.negate(secp256k1::SECP256K1)) }
 }
 
 impl StrictEncode for CompressedPk {
@@ -237,3 +253,27 @@ impl FromStr for LegacyPk {
         Ok(pk)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compressed_pk_ops() {
+        let pk = CompressedPk::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        assert_eq!(CompressedPk::from_str(&pk.to_string()).unwrap(), pk);
+
+        let neg = pk.negate();
+        assert_ne!(neg, pk);
+        assert_eq!(neg.to_byte_array()[0], 0x03);
+        assert_eq!(neg.negate(), pk);
+        assert!(pk.combine(&[neg]).is_err());
+
+        let double = pk.combine(&[pk]).unwrap();
+        assert_eq!(double.combine(&[neg]).unwrap(), pk);
+        assert_eq!(pk.combine(&[]).unwrap(), pk);
+    }
+}
@@ -19,11 +19,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt::{self, Formatter, LowerHex, UpperHex, Write};
+
 use amplify::confinement;
 use amplify::confinement::Confined;
 
 use crate::opcodes::*;
-use crate::{ScriptHash, VarInt, VarIntBytes, WitnessVer, LIB_NAME_BITCOIN};
+use crate::{
+    CompressedPk, LegacyPk, ScriptHash, UncompressedPk, VarInt, VarIntBytes, WPubkeyHash,
+    WitnessVer, LIB_NAME_BITCOIN,
+};
 
 #[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Default)]
 #[wrapper(Deref, AsSlice, Hex)]
@@ -61,9 +66,59 @@ impl SigScript {
 
     #[inline]
     pub fn as_script_bytes(&self) -> &ScriptBytes { &self.0 }
+
+    /// Constructs signature script spending a nested P2SH-P2WPKH output, which
+    /// consists of a single push of the [`RedeemScript::p2sh_p2wpkh`] redeem
+    /// script. The signature and the public key go into the witness.
+    pub fn p2sh_p2wpkh(pk: &CompressedPk) -> Self {
+        let mut script = Self::with_capacity(23);
+        script.0.push_slice(&RedeemScript::p2sh_p2wpkh(pk));
+        script
+    }
+
+    /// Checks whether the signature script reveals a P2WPKH redeem script,
+    /// i.e. spends a nested P2SH-P2WPKH output.
+    ///
+    /// As required by BIP141, the script must consist of exactly one push of
+    /// the redeem script.
+    pub fn is_p2sh_p2wpkh(&self) -> bool {
+        self.len() == 23
+            && self[0] == OP_PUSHBYTES_22
+            && RedeemScript::from_unsafe(self[1..].to_vec()).is_p2sh_wpkh()
+    }
+
+    /// Returns iterator over the data pushed by the script, like signatures,
+    /// public keys or a redeem script.
+    ///
+    /// Non-push opcodes (including `OP_1`-`OP_16`) are skipped; the iteration
+    /// stops at the first malformed instruction.
+    pub fn push_data(&self) -> impl Iterator<Item = &[u8]> {
+        self.instructions().map_while(Result::ok).filter_map(|instruction| instruction.push_bytes())
+    }
+
+    /// Returns redeem script from a P2SH input signature script, which is the
+    /// data pushed by the last instruction of the script.
+    ///
+    /// Returns `None` if the last instruction is not a data push, or if the
+    /// script or the pushed data can't be parsed into instructions.
+    ///
+    /// Since almost any byte string parses as a script, the method does not
+    /// check whether the input actually spends a P2SH output: this has to be
+    /// checked against the spent script pubkey.
+    pub fn redeem_script(&self) -> Option<RedeemScript> {
+        let mut last = None;
+        for instruction in self.instructions() {
+            last = Some(instruction.ok()?);
+        }
+        let data = last?.push_bytes()?;
+        if Instructions::new(data).any(|instruction| instruction.is_err()) {
+            return None;
+        }
+        Some(RedeemScript::from_unsafe(data.to_vec()))
+    }
 }
 
-#[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Default)]
+#[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, From, Default)]
 #[wrapper(Deref, AsSlice, Hex)]
 #[wrapper_mut(DerefMut, AsSliceMut)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
@@ -71,6 +126,16 @@ impl SigScript {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct ScriptPubkey(ScriptBytes);
 
+/// Prints the type of the script pubkey together with its hex, like
+/// `ScriptPubkey(p2tr 5120...)`.
+impl fmt::Debug for ScriptPubkey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("ScriptPubkey(")?;
+        self.write_description(f)?;
+        f.write_str(")")
+    }
+}
+
 impl TryFrom<Vec<u8>> for ScriptPubkey {
     type Error = confinement::Error;
     fn try_from(script_bytes: Vec<u8>) -> Result<Self, Self::Error> {
@@ -112,6 +177,31 @@ impl ScriptPubkey {
         script
     }
 
+    /// Constructs nested segwit P2SH-P2WPKH output script `OP_HASH160
+    /// <hash160(0x0014<wpkh>)> OP_EQUAL` for a given public key.
+    #[inline]
+    pub fn p2sh_p2wpkh(pk: &CompressedPk) -> Self {
+        RedeemScript::p2sh_p2wpkh(pk).to_script_pubkey()
+    }
+
+    /// Constructs bare public key (P2PK) output script `<pk> OP_CHECKSIG` with
+    /// a compressed public key.
+    pub fn p2pk(pk: &CompressedPk) -> Self {
+        let mut script = Self::with_capacity(35);
+        script.push_slice(&pk.to_byte_array());
+        script.push_opcode(OpCode::CheckSig);
+        script
+    }
+
+    /// Constructs bare public key (P2PK) output script `<pk> OP_CHECKSIG` with
+    /// an uncompressed public key, as used by early bitcoin transactions.
+    pub fn p2pk_uncompressed(pk: &UncompressedPk) -> Self {
+        let mut script = Self::with_capacity(67);
+        script.push_slice(&pk.to_byte_array());
+        script.push_opcode(OpCode::CheckSig);
+        script
+    }
+
     pub fn op_return(data: &[u8]) -> Self {
         let mut script = Self::with_capacity(ScriptBytes::len_for_slice(data.len()) + 1);
         script.push_opcode(OpCode::Return);
@@ -121,26 +211,77 @@ impl ScriptPubkey {
 
     /// Checks whether a script pubkey is a P2PKH output.
     #[inline]
-    pub fn is_p2pkh(&self) -> bool {
-        self.0.len() == 25
-            && self.0[0] == OP_DUP
-            && self.0[1] == OP_HASH160
-            && self.0[2] == OP_PUSHBYTES_20
-            && self.0[23] == OP_EQUALVERIFY
-            && self.0[24] == OP_CHECKSIG
-    }
+    pub fn is_p2pkh(&self) -> bool { self.as_script_ref().is_p2pkh() }
 
     /// Checks whether a script pubkey is a P2SH output.
     #[inline]
-    pub fn is_p2sh(&self) -> bool {
-        self.0.len() == 23
-            && self.0[0] == OP_HASH160
-            && self.0[1] == OP_PUSHBYTES_20
-            && self.0[22] == OP_EQUAL
-    }
+    pub fn is_p2sh(&self) -> bool { self.as_script_ref().is_p2sh() }
+
+    #[inline]
+    pub fn is_op_return(&self) -> bool { self.as_script_ref().is_op_return() }
 
+    /// Checks whether a script pubkey is a bare public key (P2PK) output.
     #[inline]
-    pub fn is_op_return(&self) -> bool { !self.is_empty() && self[0] == OpCode::Return as u8 }
+    pub fn is_p2pk(&self) -> bool { self.as_script_ref().is_p2pk() }
+
+    /// Extracts public key from a bare public key (P2PK) output script.
+    ///
+    /// Returns `None` if the script is not P2PK or the key is not a valid
+    /// public key.
+    pub fn p2pk_pubkey(&self) -> Option<LegacyPk> {
+        if !self.is_p2pk() {
+            return None;
+        }
+        LegacyPk::from_bytes(&self.as_slice()[1..self.len() - 1]).ok()
+    }
+
+    /// Returns the name of the script pubkey type: `p2pk`, `p2pkh`, `p2sh`,
+    /// `p2wpkh`, `p2wsh`, `p2tr`, `witness_program` (for other segwit versions
+    /// and program lengths), `op_return`, `empty` or `non_standard`.
+    pub fn script_type(&self) -> &'static str {
+        if self.is_p2pk() {
+            "p2pk"
+        } else if self.is_p2pkh() {
+            "p2pkh"
+        } else if self.is_p2sh() {
+            "p2sh"
+        } else if self.is_p2wpkh() {
+            "p2wpkh"
+        } else if self.is_p2wsh() {
+            "p2wsh"
+        } else if self.is_p2tr() {
+            "p2tr"
+        } else if self.is_witness_program() {
+            "witness_program"
+        } else if self.is_op_return() {
+            "op_return"
+        } else if self.is_empty() {
+            "empty"
+        } else {
+            "non_standard"
+        }
+    }
+
+    /// Describes the script pubkey by its type (see
+    /// [`ScriptPubkey::script_type`]) followed by the script hex, for use in
+    /// logs and error messages.
+    pub fn describe(&self) -> String {
+        let mut description = String::new();
+        self.write_description(&mut description).expect("writing to a string can't fail");
+        description
+    }
+
+    fn write_description(&self, f: &mut impl Write) -> fmt::Result {
+        write!(f, "{} {:x}", self.script_type(), self.0)
+    }
+
+    /// Detects whether the script contains data pushes which do not use the
+    /// minimal possible encoding (see [`Instruction::is_minimal_push`]).
+    ///
+    /// Malformed trailing instructions are ignored.
+    pub fn has_non_minimal_pushes(&self) -> bool {
+        self.instructions().any(|instr| matches!(instr, Ok(instr) if !instr.is_minimal_push()))
+    }
 
     /// Adds a single opcode to the script.
     #[inline]
@@ -181,6 +322,48 @@ impl RedeemScript {
         Self(ScriptBytes::from_unsafe(script_bytes))
     }
 
+    /// Constructs bare `k`-of-`n` multisig script `OP_k <pubkeys...> OP_n
+    /// OP_CHECKMULTISIG`, where `n` is the number of provided public keys.
+    ///
+    /// The script can be used in P2SH outputs via
+    /// [`RedeemScript::to_script_pubkey`]; for P2WSH it has to be converted
+    /// into [`crate::WitnessScript`] with `WitnessScript::from_inner(script.into_inner())`.
+    ///
+    /// # Errors
+    ///
+    /// If the condition `1 <= k <= n <= 20` is not satisfied.
+    pub fn multisig(k: u8, pubkeys: &[CompressedPk]) -> Result<Self, ScriptError> {
+        let n = pubkeys.len();
+        if n > MAX_MULTISIG_KEYS {
+            return Err(ScriptError::MultisigKeys(n));
+        }
+        if k == 0 || k as usize > n {
+            return Err(ScriptError::MultisigThreshold { k, n });
+        }
+        let mut script = Self::with_capacity(3 + n * ScriptBytes::len_for_slice(33));
+        script.push_num(k);
+        for pk in pubkeys {
+            script.push_slice(&pk.to_byte_array());
+        }
+        script.push_num(n as u8);
+        script.0.push(OP_CHECKMULTISIG);
+        Ok(script)
+    }
+
+    /// Pushes a small number (0 to 20) using the shortest opcode.
+    fn push_num(&mut self, num: u8) {
+        match num {
+            0 => self.0.push(OP_PUSHBYTES_0),
+            1..=16 => self.0.push(OP_PUSHNUM_1 + num - 1),
+            _ => self.push_slice(&[num]),
+        }
+    }
+
+    /// Constructs redeem script `0x0014<wpkh>` of a nested P2SH-P2WPKH output
+    /// for a given public key.
+    #[inline]
+    pub fn p2sh_p2wpkh(pk: &CompressedPk) -> Self { Self::p2sh_wpkh(WPubkeyHash::from(*pk)) }
+
     pub fn p2sh_wpkh(hash: impl Into<[u8; 20]>) -> Self {
         Self::with_witness_program_unchecked(WitnessVer::V0, &hash.into())
     }
@@ -215,12 +398,35 @@ impl RedeemScript {
 }
 
 #[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Debug, From)]
-#[wrapper(Deref, AsSlice, Hex)]
+#[wrapper(Deref, AsSlice, FromHex)]
 #[wrapper_mut(DerefMut, AsSliceMut)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
 pub struct ScriptBytes(VarIntBytes);
 
+impl LowerHex for ScriptBytes {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { fmt_hex(self.as_slice(), f, HEX_LOWER) }
+}
+
+impl UpperHex for ScriptBytes {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { fmt_hex(self.as_slice(), f, HEX_UPPER) }
+}
+
+const HEX_LOWER: &[u8; 16] = b"0123456789abcdef";
+const HEX_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Writes hex representation of the data directly into the formatter,
+/// nibble-by-nibble, without allocating an intermediary string.
+fn fmt_hex(data: &[u8], f: &mut Formatter<'_>, alphabet: &[u8; 16]) -> fmt::Result {
+    for byte in data {
+        f.write_char(alphabet[(byte >> 4) as usize] as char)?;
+        f.write_char(alphabet[(byte & 0x0F) as usize] as char)?;
+    }
+    Ok(())
+}
+
 impl TryFrom<Vec<u8>> for ScriptBytes {
     type Error = confinement::Error;
     fn try_from(script_bytes: Vec<u8>) -> Result<Self, Self::Error> {
@@ -292,11 +498,342 @@ impl ScriptBytes {
 
     pub fn len_var_int(&self) -> VarInt { VarInt(self.len() as u64) }
 
+    /// Returns iterator over the script instructions.
+    #[inline]
+    pub fn instructions(&self) -> Instructions<'_> { Instructions::new(self.as_slice()) }
+
+    /// Returns zero-copy read-only view over the script.
+    #[inline]
+    pub fn as_script_ref(&self) -> ScriptRef<'_> { ScriptRef::new(self.as_slice()) }
+
     pub fn into_vec(self) -> Vec<u8> { self.0.release() }
 
     pub(crate) fn as_var_int_bytes(&self) -> &VarIntBytes { &self.0 }
 }
 
+/// Zero-copy read-only view over a script data, which may be borrowed from a
+/// larger buffer (like a serialized block) without allocating [`ScriptBytes`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ScriptRef<'script>(&'script [u8]);
+
+impl AsRef<[u8]> for ScriptRef<'_> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] { self.0 }
+}
+
+impl<'script> From<&'script [u8]> for ScriptRef<'script> {
+    #[inline]
+    fn from(script: &'script [u8]) -> Self { Self(script) }
+}
+
+impl<'script> ScriptRef<'script> {
+    #[inline]
+    pub const fn new(script: &'script [u8]) -> Self { Self(script) }
+
+    #[inline]
+    pub const fn as_slice(&self) -> &'script [u8] { self.0 }
+
+    #[inline]
+    pub const fn len(&self) -> usize { self.0.len() }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Returns iterator over the script instructions.
+    #[inline]
+    pub fn instructions(&self) -> Instructions<'script> { Instructions::new(self.0) }
+
+    /// Checks whether a script is a bare public key (P2PK) output script
+    /// `<pk> OP_CHECKSIG`.
+    ///
+    /// Matches Bitcoin Core `pubkey` script type: besides compressed (`0x02`,
+    /// `0x03`) and uncompressed (`0x04`) keys, hybrid keys (`0x06`, `0x07`)
+    /// are accepted as well. Only the script structure and the key prefix
+    /// byte are checked; the key itself may be not a valid curve point.
+    #[inline]
+    pub fn is_p2pk(&self) -> bool {
+        match self.0 {
+            [len @ (OP_PUSHBYTES_33 | OP_PUSHBYTES_65), key @ .., OP_CHECKSIG] => {
+                key.len() == *len as usize && is_pubkey_sized(key)
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks whether a script is a bare multisig output script
+    /// `<m> <pk>... <n> OP_CHECKMULTISIG`, matching Bitcoin Core `multisig`
+    /// script type.
+    ///
+    /// Public keys are checked in the same way as by [`ScriptRef::is_p2pk`].
+    pub fn is_multisig(&self) -> bool {
+        let Ok(instrs) = self.instructions().collect::<Result<Vec<_>, _>>() else {
+            return false;
+        };
+        let small_int = |instr: &Instruction| match *instr {
+            Instruction::Op(op @ OP_PUSHNUM_1..=OP_PUSHNUM_16) => {
+                Some((op - OP_PUSHNUM_1 + 1) as usize)
+            }
+            _ => None,
+        };
+        let [first, keys @ .., total, Instruction::Op(OP_CHECKMULTISIG)] = instrs.as_slice() else {
+            return false;
+        };
+        let (Some(required), Some(total)) = (small_int(first), small_int(total)) else {
+            return false;
+        };
+        required <= total
+            && keys.len() == total
+            && keys.iter().all(|instr| instr.push_bytes().is_some_and(is_pubkey_sized))
+    }
+
+    /// Checks whether a script is a P2PKH output script.
+    #[inline]
+    pub fn is_p2pkh(&self) -> bool {
+        self.0.len() == 25
+            && self.0[0] == OP_DUP
+            && self.0[1] == OP_HASH160
+            && self.0[2] == OP_PUSHBYTES_20
+            && self.0[23] == OP_EQUALVERIFY
+            && self.0[24] == OP_CHECKSIG
+    }
+
+    /// Checks whether a script is a P2SH output script.
+    #[inline]
+    pub fn is_p2sh(&self) -> bool {
+        self.0.len() == 23
+            && self.0[0] == OP_HASH160
+            && self.0[1] == OP_PUSHBYTES_20
+            && self.0[22] == OP_EQUAL
+    }
+
+    /// Checks whether a script is a P2WPKH output script.
+    #[inline]
+    pub fn is_p2wpkh(&self) -> bool {
+        self.0.len() == 22
+            && self.0[0] == WitnessVer::V0.op_code() as u8
+            && self.0[1] == OP_PUSHBYTES_20
+    }
+
+    /// Checks whether a script is a P2WSH output script.
+    #[inline]
+    pub fn is_p2wsh(&self) -> bool {
+        self.0.len() == 34
+            && self.0[0] == WitnessVer::V0.op_code() as u8
+            && self.0[1] == OP_PUSHBYTES_32
+    }
+
+    /// Checks whether a script is a P2TR output script.
+    #[inline]
+    pub fn is_p2tr(&self) -> bool {
+        self.0.len() == 34
+            && self.0[0] == WitnessVer::V1.op_code() as u8
+            && self.0[1] == OP_PUSHBYTES_32
+    }
+
+    /// Checks whether a script starts with `OP_RETURN`.
+    #[inline]
+    pub fn is_op_return(&self) -> bool { !self.0.is_empty() && self.0[0] == OP_RETURN }
+
+    /// Returns version of the witness program, if the script is a witness
+    /// program (see [`ScriptRef::witness_program`]).
+    #[inline]
+    pub fn witness_version(&self) -> Option<WitnessVer> {
+        self.witness_program_parts().map(|(ver, _)| ver)
+    }
+
+    /// Returns witness program, if the script is a witness program: consists
+    /// of a version opcode (`OP_0` or `OP_1`-`OP_16`) followed by a single
+    /// push of 2 to 40 bytes, as defined in BIP141.
+    ///
+    /// The length of the program is not checked against the version-specific
+    /// rules (like the 20 or 32 bytes for the segwit v0).
+    #[inline]
+    pub fn witness_program(&self) -> Option<&'script [u8]> {
+        self.witness_program_parts().map(|(_, program)| program)
+    }
+
+    fn witness_program_parts(&self) -> Option<(WitnessVer, &'script [u8])> {
+        let [ver, push, program @ ..] = self.0 else {
+            return None;
+        };
+        if !(OP_PUSHBYTES_2..=OP_PUSHBYTES_40).contains(push) || program.len() != *push as usize {
+            return None;
+        }
+        let ver = match *ver {
+            OP_PUSHBYTES_0 => WitnessVer::V0,
+            OP_PUSHNUM_1..=OP_PUSHNUM_16 => {
+                WitnessVer::from_version_no(ver - OP_PUSHNUM_1 + 1).ok()?
+            }
+            _ => return None,
+        };
+        Some((ver, program))
+    }
+
+    /// Copies the script data into an owned [`ScriptPubkey`].
+    ///
+    /// # Panics
+    ///
+    /// If the script length exceeds 4GB.
+    #[inline]
+    pub fn to_script_pubkey(&self) -> ScriptPubkey { ScriptPubkey::from_unsafe(self.0.to_vec()) }
+}
+
+/// Checks that the public key data length matches its prefix byte, like
+/// Bitcoin Core `CPubKey::ValidSize` does.
+fn is_pubkey_sized(data: &[u8]) -> bool {
+    match data.len() {
+        33 => matches!(data[0], 0x02 | 0x03),
+        65 => matches!(data[0], 0x04 | 0x06 | 0x07),
+        _ => false,
+    }
+}
+
+/// Maximal number of public keys in `OP_CHECKMULTISIG` script.
+pub const MAX_MULTISIG_KEYS: usize = 20;
+
+/// Maximal size of data in a standard `OP_RETURN` output, matching the
+/// long-standing default of Bitcoin Core `-datacarriersize` relay policy (83
+/// bytes of the script).
+pub const MAX_OP_RETURN_DATA: usize = 80;
+
+/// Errors constructing scripts.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ScriptError {
+    /// invalid multisig threshold {k}-of-{n}: the threshold must be non-zero
+    /// and not exceed the number of public keys.
+    MultisigThreshold { k: u8, n: usize },
+
+    /// multisig script with {0} public keys exceeds the limit of 20 keys.
+    MultisigKeys(usize),
+
+    /// `OP_RETURN` data of {0} bytes exceeds the standard limit of 80 bytes.
+    OpReturnDataSize(usize),
+}
+
+/// Errors parsing script instructions.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ScriptParseError {
+    /// unexpected end of script: push opcode at position {0} requires more data
+    /// than the script contains.
+    UnexpectedEnd(usize),
+}
+
+/// Single script instruction.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Instruction<'script> {
+    /// Push of the data onto the stack, together with the opcode used for the
+    /// push (`OP_PUSHBYTES_*` or `OP_PUSHDATA*`).
+    PushBytes { opcode: u8, data: &'script [u8] },
+
+    /// Non-push opcode.
+    Op(u8),
+}
+
+impl<'script> Instruction<'script> {
+    /// Returns opcode of the instruction.
+    #[inline]
+    pub fn opcode(&self) -> u8 {
+        match *self {
+            Instruction::PushBytes { opcode, .. } | Instruction::Op(opcode) => opcode,
+        }
+    }
+
+    /// Returns data pushed by the instruction, if the instruction is a data
+    /// push.
+    #[inline]
+    pub fn push_bytes(&self) -> Option<&'script [u8]> {
+        match *self {
+            Instruction::PushBytes { data, .. } => Some(data),
+            Instruction::Op(_) => None,
+        }
+    }
+
+    /// Checks whether the data push uses the minimal possible encoding, as
+    /// required by the `MINIMALDATA` policy rule (and by consensus inside
+    /// tapscript):
+    /// - empty data must be pushed with `OP_0`;
+    /// - single byte values 1-16 and 0x81 must be pushed with `OP_1`-`OP_16` and `OP_1NEGATE`;
+    /// - data up to 75 bytes must be pushed with `OP_PUSHBYTES_N`;
+    /// - longer data must use the shortest of `OP_PUSHDATA1`, `OP_PUSHDATA2` and `OP_PUSHDATA4`.
+    ///
+    /// Always returns `true` for non-push instructions.
+    pub fn is_minimal_push(&self) -> bool {
+        let Instruction::PushBytes { opcode, data } = *self else {
+            return true;
+        };
+        match data.len() {
+            0 => opcode == OP_PUSHBYTES_0,
+            1 if (1..=16).contains(&data[0]) || data[0] == 0x81 => false,
+            len @ 1..=0x4b => opcode as usize == len,
+            0x4c..=0xff => opcode == OP_PUSHDATA1,
+            0x100..=0xffff => opcode == OP_PUSHDATA2,
+            _ => opcode == OP_PUSHDATA4,
+        }
+    }
+}
+
+/// Iterator over script instructions.
+///
+/// After the first error the iterator is exhausted.
+#[derive(Clone, Debug)]
+pub struct Instructions<'script> {
+    script: &'script [u8],
+    pos: usize,
+}
+
+impl<'script> Instructions<'script> {
+    /// Constructs iterator over instructions of raw script data.
+    #[inline]
+    pub fn new(script: &'script [u8]) -> Self { Self { script, pos: 0 } }
+
+    fn read_len(&mut self, start: usize, size: usize) -> Result<usize, ScriptParseError> {
+        let bytes = self
+            .script
+            .get(self.pos..self.pos + size)
+            .ok_or(ScriptParseError::UnexpectedEnd(start))?;
+        self.pos += size;
+        let mut buf = [0u8; 4];
+        buf[..size].copy_from_slice(bytes);
+        Ok(u32::from_le_bytes(buf) as usize)
+    }
+
+    fn next_instruction(&mut self) -> Result<Instruction<'script>, ScriptParseError> {
+        let start = self.pos;
+        let opcode = self.script[start];
+        self.pos += 1;
+        let len = match opcode {
+            OP_PUSHBYTES_0..=OP_PUSHBYTES_75 => opcode as usize,
+            OP_PUSHDATA1 => self.read_len(start, 1)?,
+            OP_PUSHDATA2 => self.read_len(start, 2)?,
+            OP_PUSHDATA4 => self.read_len(start, 4)?,
+            _ => return Ok(Instruction::Op(opcode)),
+        };
+        let data = self
+            .script
+            .get(self.pos..self.pos.saturating_add(len))
+            .ok_or(ScriptParseError::UnexpectedEnd(start))?;
+        self.pos += len;
+        Ok(Instruction::PushBytes { opcode, data })
+    }
+}
+
+impl<'script> Iterator for Instructions<'script> {
+    type Item = Result<Instruction<'script>, ScriptParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.script.len() {
+            return None;
+        }
+        let res = self.next_instruction();
+        if res.is_err() {
+            self.pos = self.script.len();
+        }
+        Some(res)
+    }
+}
+
 #[cfg(feature = "serde")]
 mod _serde {
     use amplify::hex::{FromHex, ToHex};
@@ -334,9 +871,233 @@ mod _serde {
 
 #[cfg(test)]
 mod test {
+    use std::str::FromStr;
+
     use amplify::hex::ToHex;
 
     use super::*;
+    use crate::InternalPk;
+
+    #[test]
+    fn p2pk() {
+        let compressed = CompressedPk::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        let spk = ScriptPubkey::p2pk(&compressed);
+        assert_eq!(
+            spk.to_hex(),
+            "210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac"
+        );
+        assert!(spk.is_p2pk());
+        assert_eq!(spk.script_type(), "p2pk");
+        assert_eq!(spk.p2pk_pubkey(), Some(LegacyPk::from(compressed)));
+
+        let uncompressed =
+            UncompressedPk::from_byte_array(compressed.serialize_uncompressed()).unwrap();
+        let spk = ScriptPubkey::p2pk_uncompressed(&uncompressed);
+        assert_eq!(spk.len(), 67);
+        assert!(spk.is_p2pk());
+        assert_eq!(spk.p2pk_pubkey(), Some(LegacyPk::from(uncompressed)));
+
+        // Mismatched key length prefix
+        let mut data = spk.to_vec();
+        data[1] = 0x02;
+        assert!(!ScriptPubkey::from_unsafe(data).is_p2pk());
+        // Not a valid curve point
+        let mut data = vec![OP_PUSHBYTES_33, 0x02];
+        data.extend([0xFF; 32]);
+        data.push(OP_CHECKSIG);
+        let spk = ScriptPubkey::from_unsafe(data);
+        assert!(spk.is_p2pk());
+        assert_eq!(spk.p2pk_pubkey(), None);
+        assert!(!ScriptPubkey::p2pkh([1u8; 20]).is_p2pk());
+        // Hybrid keys are classified as P2PK, like in Bitcoin Core
+        let mut data = vec![OP_PUSHBYTES_65, 0x07];
+        data.extend([0xAB; 64]);
+        data.push(OP_CHECKSIG);
+        assert!(ScriptPubkey::from_unsafe(data).is_p2pk());
+        // Push length not matching the key length
+        let mut data = vec![OP_PUSHBYTES_33, 0x04];
+        data.extend([0xAB; 64]);
+        data.push(OP_CHECKSIG);
+        assert!(!ScriptPubkey::from_unsafe(data).is_p2pk());
+    }
+
+    #[test]
+    fn bare_multisig_classifier() {
+        let mut data = vec![OP_PUSHNUM_2];
+        for prefix in [0x02, 0x03] {
+            data.push(OP_PUSHBYTES_33);
+            data.push(prefix);
+            data.extend([0xAB; 32]);
+        }
+        data.extend([OP_PUSHNUM_2, OP_CHECKMULTISIG]);
+        assert!(ScriptRef::new(&data).is_multisig());
+
+        // More signatures required than there are keys
+        let mut invalid = data.clone();
+        invalid[0] = OP_PUSHNUM_3;
+        assert!(!ScriptRef::new(&invalid).is_multisig());
+        // Number of keys not matching the declared total
+        let mut invalid = data.clone();
+        let len = invalid.len();
+        invalid[len - 2] = OP_PUSHNUM_3;
+        assert!(!ScriptRef::new(&invalid).is_multisig());
+        // Not a public key
+        let mut invalid = data;
+        invalid[2] = 0x05;
+        assert!(!ScriptRef::new(&invalid).is_multisig());
+    }
+
+    #[test]
+    fn p2sh_p2wpkh() {
+        // BIP143 P2SH-P2WPKH test vector
+        let pk = CompressedPk::from_str(
+            "03ad1d8e89212f0b92c74d23bb710c00662ad1470198ac48c43f7d6f93a2a26873",
+        )
+        .unwrap();
+        let redeem_script = RedeemScript::p2sh_p2wpkh(&pk);
+        assert_eq!(redeem_script.to_hex(), "001479091972186c449eb1ded22b78e40d009bdf0089");
+        assert!(redeem_script.is_p2sh_wpkh());
+
+        let spk = ScriptPubkey::p2sh_p2wpkh(&pk);
+        assert_eq!(spk.to_hex(), "a9144733f37cf4db86fbc2efed2500b4f4e49f31202387");
+        assert!(spk.is_p2sh());
+
+        let sig_script = SigScript::p2sh_p2wpkh(&pk);
+        assert_eq!(sig_script.to_hex(), "16001479091972186c449eb1ded22b78e40d009bdf0089");
+        assert!(sig_script.is_p2sh_p2wpkh());
+        assert_eq!(sig_script.redeem_script(), Some(redeem_script));
+
+        // Additional pushes are not allowed for nested segwit spends
+        let mut data = vec![OP_PUSHBYTES_0];
+        data.extend(sig_script.to_vec());
+        assert!(!SigScript::from_unsafe(data).is_p2sh_p2wpkh());
+        // P2SH-P2WSH redeem script
+        let mut sig_script = SigScript::new();
+        sig_script.0.push_slice(&RedeemScript::p2sh_wsh([1u8; 32]));
+        assert!(!sig_script.is_p2sh_p2wpkh());
+    }
+
+    #[test]
+    fn script_type() {
+        assert_eq!(ScriptPubkey::p2pkh([1u8; 20]).script_type(), "p2pkh");
+        assert_eq!(ScriptPubkey::p2sh([1u8; 20]).script_type(), "p2sh");
+        assert_eq!(ScriptPubkey::p2wpkh([1u8; 20]).script_type(), "p2wpkh");
+        assert_eq!(ScriptPubkey::p2wsh([1u8; 32]).script_type(), "p2wsh");
+        assert_eq!(
+            ScriptPubkey::from_unsafe(vec![OP_PUSHNUM_16, OP_PUSHBYTES_2, 0xAB, 0xCD])
+                .script_type(),
+            "witness_program"
+        );
+        assert_eq!(ScriptPubkey::new().script_type(), "empty");
+        assert_eq!(ScriptPubkey::from_unsafe(vec![OP_PUSHNUM_1]).script_type(), "non_standard");
+
+        let spk = ScriptPubkey::op_return(&[0xAB]);
+        assert_eq!(spk.describe(), "op_return 6a01ab");
+        assert_eq!(format!("{spk:?}"), "ScriptPubkey(op_return 6a01ab)");
+
+        let spk = ScriptPubkey::p2tr_key_only(InternalPk::nums());
+        assert!(format!("{spk:?}").starts_with("ScriptPubkey(p2tr 5120"));
+    }
+
+    #[test]
+    fn witness_program() {
+        let spk = ScriptPubkey::p2wpkh([1u8; 20]);
+        assert_eq!(spk.witness_version(), Some(WitnessVer::V0));
+        assert_eq!(spk.witness_program(), Some(&[1u8; 20][..]));
+
+        let spk = ScriptPubkey::from_unsafe(vec![OP_PUSHNUM_16, OP_PUSHBYTES_2, 0xAB, 0xCD]);
+        assert!(spk.is_witness_program());
+        assert_eq!(spk.witness_version(), Some(WitnessVer::V16));
+        assert_eq!(spk.witness_program(), Some(&[0xAB, 0xCD][..]));
+
+        let mut data = vec![OP_PUSHNUM_2, OP_PUSHBYTES_40];
+        data.extend([0u8; 40]);
+        let spk = ScriptPubkey::from_unsafe(data.clone());
+        assert_eq!(spk.witness_version(), Some(WitnessVer::V2));
+        data.push(0);
+        data[1] = OP_PUSHBYTES_41;
+        assert_eq!(ScriptPubkey::from_unsafe(data).witness_version(), None);
+
+        for data in [
+            vec![OP_PUSHNUM_1, OP_PUSHBYTES_2, 0xAB],
+            vec![OP_PUSHNUM_1, OP_PUSHBYTES_1, 0xAB],
+            vec![OP_PUSHNUM_NEG1, OP_PUSHBYTES_2, 0xAB, 0xCD],
+            vec![OP_RESERVED, OP_PUSHBYTES_2, 0xAB, 0xCD],
+            vec![OP_PUSHNUM_1],
+        ] {
+            let spk = ScriptPubkey::from_unsafe(data);
+            assert!(!spk.is_witness_program());
+            assert_eq!(spk.witness_program(), None);
+        }
+    }
+
+    #[test]
+    fn sig_script_pushes() {
+        let sig = [0x30u8; 71];
+        let pk = [0x02u8; 33];
+        let mut sig_script = SigScript::new();
+        sig_script.push_slice(&sig);
+        sig_script.push_slice(&pk);
+        assert_eq!(sig_script.push_data().collect::<Vec<_>>(), vec![&sig[..], &pk[..]]);
+
+        let redeem_script = RedeemScript::p2sh_wpkh([7u8; 20]);
+        let mut sig_script = SigScript::new();
+        sig_script.push_slice(redeem_script.as_slice());
+        assert_eq!(sig_script.redeem_script(), Some(redeem_script));
+
+        let mut sig_script = SigScript::new();
+        sig_script.push_slice(&[OP_PUSHBYTES_32, 1, 2]);
+        assert_eq!(sig_script.push_data().count(), 1);
+        assert_eq!(sig_script.redeem_script(), None);
+
+        let sig_script = SigScript::from_unsafe(vec![OP_PUSHBYTES_1, 1, OP_PUSHNUM_1]);
+        assert_eq!(sig_script.push_data().collect::<Vec<_>>(), vec![&[1u8][..]]);
+        assert_eq!(sig_script.redeem_script(), None);
+
+        let sig_script = SigScript::from_unsafe(vec![OP_PUSHBYTES_1, 1, OP_PUSHBYTES_2, 1]);
+        assert_eq!(sig_script.push_data().count(), 1);
+        assert_eq!(sig_script.redeem_script(), None);
+    }
+
+    #[test]
+    fn multisig() {
+        let pks = [
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        ]
+        .map(|s| CompressedPk::from_str(s).unwrap());
+
+        let script = RedeemScript::multisig(2, &pks).unwrap();
+        assert_eq!(script.len(), 3 + 3 * 34);
+        assert_eq!(script[0], OP_PUSHNUM_2);
+        assert_eq!(script[1], OP_PUSHBYTES_33);
+        assert_eq!(&script[2..35], &pks[0].to_byte_array());
+        assert_eq!(script[script.len() - 2], OP_PUSHNUM_3);
+        assert_eq!(script[script.len() - 1], OP_CHECKMULTISIG);
+        assert!(script.to_script_pubkey().is_p2sh());
+
+        assert_eq!(RedeemScript::multisig(1, &pks[..1]).unwrap()[0], OP_PUSHNUM_1);
+        assert_eq!(RedeemScript::multisig(0, &pks).unwrap_err(), ScriptError::MultisigThreshold {
+            k: 0,
+            n: 3
+        });
+        assert_eq!(RedeemScript::multisig(4, &pks).unwrap_err(), ScriptError::MultisigThreshold {
+            k: 4,
+            n: 3
+        });
+        assert_eq!(
+            RedeemScript::multisig(1, &[pks[0]; 21]).unwrap_err(),
+            ScriptError::MultisigKeys(21)
+        );
+
+        let script = RedeemScript::multisig(17, &[pks[0]; 20]).unwrap();
+        assert_eq!(&script[..2], &[OP_PUSHBYTES_1, 17]);
+        assert_eq!(&script[script.len() - 3..], &[OP_PUSHBYTES_1, 20, OP_CHECKMULTISIG]);
+    }
 
     #[test]
     fn script_index() {
@@ -364,4 +1125,64 @@ mod test {
             "ffffffff000000000000000000000000000000000000000000000000000000000000000000000000ffff"
         );
     }
+
+    #[test]
+    fn instructions() {
+        let script = ScriptPubkey::p2pkh([0xAB; 20]);
+        let instructions = script.instructions().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(instructions, vec![
+            Instruction::Op(OP_DUP),
+            Instruction::Op(OP_HASH160),
+            Instruction::PushBytes {
+                opcode: OP_PUSHBYTES_20,
+                data: &[0xAB; 20]
+            },
+            Instruction::Op(OP_EQUALVERIFY),
+            Instruction::Op(OP_CHECKSIG),
+        ]);
+        assert!(!script.has_non_minimal_pushes());
+
+        let script = ScriptPubkey::from_unsafe(vec![OP_RETURN, OP_PUSHDATA1, 0x02, 0xAB]);
+        assert_eq!(script.instructions().collect::<Vec<_>>(), vec![
+            Ok(Instruction::Op(OP_RETURN)),
+            Err(ScriptParseError::UnexpectedEnd(1))
+        ]);
+    }
+
+    #[test]
+    fn script_ref() {
+        let block_data =
+            [&[0xFFu8, 0xFF][..], ScriptPubkey::p2tr_key_only(InternalPk::nums()).as_slice()]
+                .concat();
+        let script = ScriptRef::new(&block_data[2..]);
+        assert!(script.is_p2tr());
+        assert!(!script.is_p2wsh());
+        assert!(!script.is_op_return());
+        assert_eq!(script.instructions().count(), 2);
+        assert_eq!(script.to_script_pubkey().as_script_ref(), script);
+    }
+
+    #[test]
+    fn non_minimal_push() {
+        let script = ScriptPubkey::from_unsafe(vec![OP_RETURN, OP_PUSHDATA1, 0x01, 0xAB]);
+        assert!(script.has_non_minimal_pushes());
+        let script = ScriptPubkey::from_unsafe(vec![OP_RETURN, OP_PUSHBYTES_1, 0xAB]);
+        assert!(!script.has_non_minimal_pushes());
+        let script = ScriptPubkey::from_unsafe(vec![OP_RETURN, OP_PUSHBYTES_1, 0x10]);
+        assert!(script.has_non_minimal_pushes());
+        let script = ScriptPubkey::from_unsafe(vec![OP_RETURN, OP_PUSHDATA1, 0x00]);
+        assert!(script.has_non_minimal_pushes());
+    }
+
+    #[test]
+    fn script_bytes_hex() {
+        let script = ScriptBytes::from_unsafe(vec![0x00, 0x0F, 0xA5, 0xFF]);
+        assert_eq!(format!("{script:x}"), "000fa5ff");
+        assert_eq!(format!("{script:X}"), "000FA5FF");
+        assert_eq!(script.to_hex(), "000fa5ff");
+        assert_eq!(format!("{:x}", ScriptBytes::default()), "");
+
+        let spk = ScriptPubkey::p2wpkh([0xAB; 20]);
+        assert_eq!(format!("{spk:x}"), format!("0014{}", "ab".repeat(20)));
+    }
 }
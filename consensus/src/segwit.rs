@@ -22,11 +22,12 @@
 use std::vec;
 
 use amplify::confinement::Confined;
-use amplify::{confinement, Bytes32StrRev, Wrapper};
+use amplify::{confinement, Bytes32, Bytes32StrRev, Wrapper};
 
 use crate::opcodes::*;
 use crate::{
-    ByteStr, RedeemScript, ScriptBytes, ScriptPubkey, VarIntArray, WScriptHash, LIB_NAME_BITCOIN,
+    Annex, Bip340Sig, ByteStr, ConsensusEncode, ControlBlock, LeafScript, RedeemScript,
+    ScriptBytes, ScriptPubkey, Sha256d, SigScript, VarIntArray, WScriptHash, LIB_NAME_BITCOIN,
 };
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
@@ -257,13 +258,9 @@ impl ScriptPubkey {
         Self::with_witness_program_unchecked(WitnessVer::V0, &hash.into())
     }
 
-    pub fn is_p2wpkh(&self) -> bool {
-        self.len() == 22 && self[0] == WitnessVer::V0.op_code() as u8 && self[1] == OP_PUSHBYTES_20
-    }
+    pub fn is_p2wpkh(&self) -> bool { self.as_script_ref().is_p2wpkh() }
 
-    pub fn is_p2wsh(&self) -> bool {
-        self.len() == 34 && self[0] == WitnessVer::V0.op_code() as u8 && self[1] == OP_PUSHBYTES_32
-    }
+    pub fn is_p2wsh(&self) -> bool { self.as_script_ref().is_p2wsh() }
 
     /// Generates P2WSH-type of scriptPubkey with a given [`WitnessProgram`].
     pub fn from_witness_program(witness_program: &WitnessProgram) -> Self {
@@ -281,26 +278,24 @@ impl ScriptPubkey {
 
     /// Checks whether a script pubkey is a Segregated Witness (segwit) program.
     #[inline]
-    pub fn is_witness_program(&self) -> bool {
-        // A scriptPubKey (or redeemScript as defined in BIP16/P2SH) that consists of a
-        // 1-byte push opcode (for 0 to 16) followed by a data push between 2
-        // and 40 bytes gets a new special meaning. The value of the first push
-        // is called the "version byte". The following byte vector pushed is
-        // called the "witness program".
-        let script_len = self.len();
-        if !(4..=42).contains(&script_len) {
-            return false;
-        }
-        // Version 0 or PUSHNUM_1-PUSHNUM_16
-        let Ok(ver_opcode) = OpCode::try_from(self[0]) else {
-            return false;
-        };
-        let push_opbyte = self[1]; // Second byte push opcode 2-40 bytes
-        WitnessVer::from_op_code(ver_opcode).is_ok()
-            && (OP_PUSHBYTES_2..=OP_PUSHBYTES_40).contains(&push_opbyte)
-            // Check that the rest of the script has the correct size
-            && script_len - 2 == push_opbyte as usize
-    }
+    pub fn is_witness_program(&self) -> bool { self.witness_version().is_some() }
+
+    /// Returns version of the witness program, if the script pubkey is a
+    /// Segregated Witness (segwit) program.
+    ///
+    /// Future witness versions (2 to 16) are returned as well; callers which
+    /// must accept only the known output types should check the version
+    /// explicitly.
+    #[inline]
+    pub fn witness_version(&self) -> Option<WitnessVer> { self.as_script_ref().witness_version() }
+
+    /// Returns witness program (without the version and push opcodes), if
+    /// the script pubkey is a Segregated Witness (segwit) program: consists of
+    /// a version opcode followed by a single push of 2 to 40 bytes.
+    ///
+    /// The program length is not checked against version-specific rules.
+    #[inline]
+    pub fn witness_program(&self) -> Option<&[u8]> { self.as_script_ref().witness_program() }
 }
 
 #[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Default)]
@@ -345,10 +340,48 @@ impl WitnessScript {
 
     pub fn to_script_pubkey(&self) -> ScriptPubkey { ScriptPubkey::p2wsh(WScriptHash::from(self)) }
 
+    /// Computes all scripts for the P2WSH output nested into P2SH
+    /// (`sh(wsh(...))` descriptor).
+    pub fn to_sh_wsh(&self) -> ShWsh {
+        let redeem_script = self.to_redeem_script();
+        ShWsh {
+            witness_script: self.clone(),
+            script_pubkey: redeem_script.to_script_pubkey(),
+            redeem_script,
+        }
+    }
+
     #[inline]
     pub fn as_script_bytes(&self) -> &ScriptBytes { &self.0 }
 }
 
+/// Scripts of a P2WSH output nested into P2SH (`sh(wsh(...))` descriptor).
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ShWsh {
+    /// Witness script, which is put as the last element of the witness stack
+    /// when spending the output.
+    pub witness_script: WitnessScript,
+    /// Redeem script `OP_0 <sha256(witness_script)>`.
+    pub redeem_script: RedeemScript,
+    /// Script pubkey `OP_HASH160 <hash160(redeem_script)> OP_EQUAL`.
+    pub script_pubkey: ScriptPubkey,
+}
+
+impl ShWsh {
+    /// Constructs all scripts from a witness script.
+    #[inline]
+    pub fn new(witness_script: WitnessScript) -> Self { witness_script.to_sh_wsh() }
+
+    /// Constructs signature script for spending the output, which consists of
+    /// a single push of the redeem script.
+    pub fn to_sig_script(&self) -> SigScript {
+        let mut sig_script =
+            SigScript::with_capacity(ScriptBytes::len_for_slice(self.redeem_script.len()));
+        sig_script.push_slice(self.redeem_script.as_slice());
+        sig_script
+    }
+}
+
 #[derive(Wrapper, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, From)]
 #[wrapper(BorrowSlice, Index, RangeOps, Debug, Hex, Display, FromStr)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -389,10 +422,104 @@ impl Witness {
         Witness(stack)
     }
 
+    /// Constructs witness for a taproot key path spend, consisting of the
+    /// serialized signature followed by an optional annex.
+    pub fn for_taproot_key_spend(sig: &Bip340Sig, annex: Option<Annex>) -> Self {
+        Witness::from_consensus_stack([sig.to_vec()].into_iter().chain(annex.map(Annex::into_vec)))
+    }
+
+    /// Constructs witness for a taproot script path spend, consisting of the
+    /// `stack` items (which must satisfy the leaf script), followed by the
+    /// leaf script and serialized control block.
+    ///
+    /// The leaf version of the `script` must match the one in the
+    /// `control_block`. Use [`Witness::with_annex`] to add annex.
+    pub fn for_tapscript_spend(
+        stack: Vec<Vec<u8>>,
+        script: &LeafScript,
+        control_block: &ControlBlock,
+    ) -> Self {
+        debug_assert_eq!(script.version, control_block.leaf_version);
+        Witness::from_consensus_stack(
+            stack.into_iter().chain([script.script.to_vec(), control_block.consensus_serialize()]),
+        )
+    }
+
+    /// Appends annex as the last witness element.
+    ///
+    /// # Panics
+    ///
+    /// If the witness stack already has 2^32 elements.
+    #[must_use]
+    pub fn with_annex(mut self, annex: &Annex) -> Self {
+        self.push(annex.to_vec()).expect("witness stack size exceeds 2^32 elements");
+        self
+    }
+
+    /// Adds an element to the end of the witness stack.
+    ///
+    /// # Errors
+    ///
+    /// If the witness stack already has 2^32 elements.
+    #[inline]
+    pub fn push(&mut self, element: impl Into<ByteStr>) -> Result<(), confinement::Error> {
+        self.0.push(element.into())
+    }
+
     #[inline]
     pub(crate) fn as_var_int_array(&self) -> &VarIntArray<ByteStr> { &self.0 }
 }
 
+/// Prefix of the coinbase transaction output script containing
+/// [`WitnessCommitment`]: `OP_RETURN OP_PUSHBYTES_36` followed by the
+/// `0xaa21a9ed` commitment header, as defined in BIP-141.
+pub const WITNESS_COMMITMENT_PREFIX: [u8; 6] = [OP_RETURN, OP_PUSHBYTES_36, 0xaa, 0x21, 0xa9, 0xed];
+
+/// Commitment to the witness data of the block transactions, placed into an
+/// output of the coinbase transaction (BIP-141).
+#[derive(Wrapper, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, From)]
+#[wrapper(BorrowSlice, Index, RangeOps, Hex)]
+pub struct WitnessCommitment(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl WitnessCommitment {
+    /// Computes witness commitment as a double SHA256 hash of the witness
+    /// merkle root concatenated with the witness reserved value, which must
+    /// be the only element of the coinbase input witness.
+    pub fn compute(witness_root: [u8; 32], reserved_value: [u8; 32]) -> Self {
+        let mut enc = Sha256d::new();
+        enc.input_raw(&witness_root);
+        enc.input_raw(&reserved_value);
+        Self::from(enc.finish())
+    }
+
+    /// Extracts witness commitment from a coinbase output script.
+    ///
+    /// The script must start with [`WITNESS_COMMITMENT_PREFIX`] followed by
+    /// the 32-byte commitment; any trailing data are ignored, as required by
+    /// BIP-141.
+    pub fn from_script_pubkey(script_pubkey: &ScriptPubkey) -> Option<Self> {
+        let script = script_pubkey.as_slice();
+        if script.len() < 38 || script[..6] != WITNESS_COMMITMENT_PREFIX {
+            return None;
+        }
+        Bytes32::copy_from_slice(&script[6..38]).ok().map(Self)
+    }
+
+    /// Constructs coinbase output script containing the commitment.
+    pub fn to_script_pubkey(&self) -> ScriptPubkey {
+        let mut script = WITNESS_COMMITMENT_PREFIX.to_vec();
+        script.extend_from_slice(self.0.as_slice());
+        ScriptPubkey::from_unsafe(script)
+    }
+
+    #[inline]
+    pub fn to_byte_array(&self) -> [u8; 32] { self.0.to_byte_array() }
+}
+
 #[cfg(feature = "serde")]
 mod _serde {
     use serde::ser::SerializeSeq;
@@ -419,3 +546,58 @@ mod _serde {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ScriptHash;
+
+    #[test]
+    fn sh_wsh() {
+        let witness_script = WitnessScript::from_unsafe(vec![OP_PUSHNUM_1]);
+        let scripts = ShWsh::new(witness_script.clone());
+        assert_eq!(scripts.witness_script, witness_script);
+        assert!(scripts.redeem_script.is_p2sh_wsh());
+        assert_eq!(
+            scripts.redeem_script[2..],
+            <[u8; 32]>::from(WScriptHash::from(&witness_script))
+        );
+        assert!(scripts.script_pubkey.is_p2sh());
+        assert_eq!(
+            scripts.script_pubkey,
+            ScriptPubkey::p2sh(ScriptHash::from(&scripts.redeem_script))
+        );
+        let sig_script = scripts.to_sig_script();
+        assert_eq!(sig_script.len(), 35);
+        assert_eq!(sig_script.redeem_script(), Some(scripts.redeem_script));
+    }
+
+    #[test]
+    fn witness_push() {
+        let mut witness = Witness::new();
+        witness.push(vec![0xAA; 72]).unwrap();
+        witness.push(ByteStr::from(vec![0x02; 33])).unwrap();
+        assert_eq!(witness, Witness::from_consensus_stack([vec![0xAA; 72], vec![0x02; 33]]));
+    }
+
+    #[test]
+    fn witness_commitment() {
+        let commitment = WitnessCommitment::compute([0x11; 32], [0u8; 32]);
+        let script = commitment.to_script_pubkey();
+        assert_eq!(script.len(), 38);
+        assert!(script.is_op_return());
+        assert_eq!(WitnessCommitment::from_script_pubkey(&script), Some(commitment));
+
+        let mut data = script.to_vec();
+        data.push(0xFF);
+        let extended = ScriptPubkey::from_unsafe(data);
+        assert_eq!(WitnessCommitment::from_script_pubkey(&extended), Some(commitment));
+
+        let truncated = ScriptPubkey::from_unsafe(script[..37].to_vec());
+        assert_eq!(WitnessCommitment::from_script_pubkey(&truncated), None);
+        assert_eq!(
+            WitnessCommitment::from_script_pubkey(&ScriptPubkey::op_return(&[0u8; 36])),
+            None
+        );
+    }
+}
@@ -29,7 +29,8 @@ use crate::{
     SighashType, TapLeafHash, TapSighash, Tx as Transaction, TxIn, TxOut, Txid, VarIntArray,
 };
 
-/// Used for signature hash for invalid use of SIGHASH_SINGLE.
+/// Used for signature hash for invalid use of SIGHASH_SINGLE: a 256-bit number
+/// one, serialized in little-endian byte order.
 const UINT256_ONE: [u8; 32] = [
     1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
@@ -70,6 +71,10 @@ impl From<IoError> for SighashError {
 
 /// Efficiently calculates signature hash message for legacy, segwit and taproot
 /// inputs.
+///
+/// Methods are typed by the kind of the input they sign: legacy and segwit v0
+/// methods return [`Sighash`], while taproot methods (`tap_sighash_*`) return
+/// [`TapSighash`]. Both types convert into [`secp256k1::Message`].
 #[derive(Debug)]
 pub struct SighashCache<Prevout: Borrow<TxOut> = TxOut, Tx: Borrow<Transaction> = Transaction> {
     /// Access to transaction required for transaction introspection.
@@ -142,6 +147,12 @@ impl<Prevout: Borrow<TxOut>, Tx: Borrow<Transaction>> SighashCache<Prevout, Tx>
 
     /// Computes the BIP341 sighash for any type with a fine-grained control
     /// over annex and code separator.
+    ///
+    /// `sighash_type` of `None` stands for `SIGHASH_DEFAULT`, which commits to
+    /// the same transaction data as `SIGHASH_ALL`, but produces a different
+    /// sighash, since the sighash type byte is a part of the hashed data.
+    ///
+    /// The returned [`TapSighash`] must be signed with BIP340 signature.
     pub fn tap_sighash_custom(
         &mut self,
         input_index: usize,
@@ -282,6 +293,20 @@ impl<Prevout: Borrow<TxOut>, Tx: Borrow<Transaction>> SighashCache<Prevout, Tx>
         self.tap_sighash_custom(input_index, None, None, sighash_type)
     }
 
+    /// Computes the BIP341 sighash for the most common case of a key path
+    /// spend with `SIGHASH_DEFAULT` sighash type and no annex.
+    ///
+    /// This is the same as calling [`Self::tap_sighash_key`] with `None` for
+    /// the sighash type. The produced BIP340 signature must be serialized
+    /// without the sighash type byte (i.e. as a 64-byte signature).
+    #[inline]
+    pub fn taproot_key_spend_sighash(
+        &mut self,
+        input_index: usize,
+    ) -> Result<TapSighash, SighashError> {
+        self.tap_sighash_key(input_index, None)
+    }
+
     /// Computes the BIP341 sighash for a script spend.
     ///
     /// Assumes the default `OP_CODESEPARATOR` position of `0xFFFFFFFF`.
@@ -300,6 +325,8 @@ impl<Prevout: Borrow<TxOut>, Tx: Borrow<Transaction>> SighashCache<Prevout, Tx>
     }
 
     /// Computes the BIP143 sighash for any flag type.
+    ///
+    /// The returned [`Sighash`] must be signed with ECDSA signature.
     pub fn segwit_sighash(
         &mut self,
         input_index: usize,
@@ -366,6 +393,19 @@ impl<Prevout: Borrow<TxOut>, Tx: Borrow<Transaction>> SighashCache<Prevout, Tx>
     }
 
     /// Computes the legacy sighash for any `sighash_type`.
+    ///
+    /// The returned [`Sighash`] must be signed with ECDSA signature.
+    ///
+    /// # `SIGHASH_SINGLE` bug
+    ///
+    /// If `SIGHASH_SINGLE` is used for an input which index is not less than
+    /// the number of transaction outputs, the method returns the value of
+    /// `0x0000...0001` (i.e. number one serialized as a little-endian 256-bit
+    /// integer) instead of an error. This replicates a bug in the original
+    /// Bitcoin implementation which became a part of the consensus rules: a
+    /// signature over this value is a valid one for such inputs, and there are
+    /// transactions in the blockchain relying on it. Segwit and taproot
+    /// sighash algorithms do not have this bug.
     pub fn legacy_sighash(
         &self,
         input_index: usize,
@@ -488,3 +528,119 @@ impl<Prevout: Borrow<TxOut>, Tx: Borrow<Transaction>> SighashCache<Prevout, Tx>
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::FromHex;
+    use secp256k1::Message;
+
+    use super::*;
+    use crate::{LockTime, Outpoint, TxVer};
+
+    fn tx() -> Transaction {
+        Transaction {
+            version: TxVer::V2,
+            inputs: VarIntArray::from_checked(vec![TxIn {
+                prev_output: Outpoint::coinbase(),
+                sig_script: none!(),
+                sequence: SeqNo::ZERO,
+                witness: none!(),
+            }]),
+            outputs: none!(),
+            lock_time: LockTime::ZERO,
+        }
+    }
+
+    #[test]
+    fn typed_sighashes() {
+        let prevout = TxOut::new(ScriptPubkey::new(), Sats::ZERO);
+        let mut cache = SighashCache::new(tx(), vec![prevout.clone()]).unwrap();
+
+        let script_code = ScriptCode::with_p2wpkh(&ScriptPubkey::p2wpkh([0u8; 20]));
+
+        let legacy: Sighash = cache.legacy_sighash(0, &prevout.script_pubkey, 0x01).unwrap();
+        let segwit: Sighash =
+            cache.segwit_sighash(0, &script_code, Sats::ZERO, SighashType::all()).unwrap();
+        let taproot: TapSighash = cache.tap_sighash_key(0, None).unwrap();
+
+        assert_eq!(Message::from(legacy).as_ref(), &<[u8; 32]>::from(legacy));
+        assert_eq!(Message::from(segwit).as_ref(), &<[u8; 32]>::from(segwit));
+        assert_eq!(Message::from(taproot).as_ref(), &<[u8; 32]>::from(taproot));
+
+        // SIGHASH_DEFAULT and SIGHASH_ALL commit to different hash type bytes
+        assert_ne!(cache.tap_sighash_key(0, Some(SighashType::ALL)).unwrap(), taproot);
+        assert_eq!(cache.taproot_key_spend_sighash(0).unwrap(), taproot);
+    }
+
+    #[test]
+    fn taproot_key_spend_sighash() {
+        let script_pubkey = ScriptPubkey::from_unsafe([&[0x51, 0x20][..], &[0xAB; 32]].concat());
+        let mut tx = tx();
+        tx.outputs =
+            VarIntArray::from_checked(vec![TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64)]);
+        let mut cache = SighashCache::new(tx, vec![TxOut::new(script_pubkey, 50_000u64)]).unwrap();
+
+        // Computed independently following BIP341 signature message algorithm
+        let sighash = cache.taproot_key_spend_sighash(0).unwrap();
+        assert_eq!(
+            <[u8; 32]>::from(sighash),
+            <[u8; 32]>::from_hex(
+                "9b1fb9216f8279058d2ff3a22e9d75d4147152c74261cfbf32c439e6e8eeb7d8"
+            )
+            .unwrap()
+        );
+        assert_eq!(sighash, cache.tap_sighash_key(0, None).unwrap());
+    }
+
+    #[test]
+    fn tap_sighash_annex() {
+        let prevout = TxOut::new(ScriptPubkey::new(), Sats::ZERO);
+        let tx = tx();
+        let mut cache = SighashCache::new(tx.clone(), vec![prevout.clone()]).unwrap();
+        let annex = Annex::new(vec![0x50, 0xAA]).unwrap();
+
+        let without = cache.tap_sighash_key(0, None).unwrap();
+        let with = cache.tap_sighash_custom(0, Some(annex.clone()), None, None).unwrap();
+        assert_ne!(with, without);
+
+        // BIP341 signature message for SIGHASH_DEFAULT key path spend with annex
+        let mut engine = TapSighash::engine();
+        engine.input_raw(&[0x00, 0x00]); // epoch and hash type
+        engine.input_raw(&tx.version.consensus_serialize());
+        engine.input_raw(&tx.lock_time.consensus_serialize());
+        engine.input_raw(&Sha256::digest(tx.inputs[0].prev_output.consensus_serialize()));
+        engine.input_raw(&Sha256::digest(prevout.value.consensus_serialize()));
+        engine.input_raw(&Sha256::digest(prevout.script_pubkey.consensus_serialize()));
+        engine.input_raw(&Sha256::digest(tx.inputs[0].sequence.consensus_serialize()));
+        engine.input_raw(&Sha256::digest(b""));
+        engine.input_raw(&[0x01]); // spend type: key path with annex
+        engine.input_raw(&0u32.to_le_bytes());
+        // Annex is hashed together with its compact size prefix
+        engine.input_raw(&Sha256::digest([0x02u8, 0x50, 0xAA]));
+        assert_eq!(with, TapSighash::from_engine(engine));
+    }
+
+    #[test]
+    fn legacy_sighash_single_bug() {
+        let prevout = TxOut::new(ScriptPubkey::new(), Sats::ZERO);
+        let cache = SighashCache::new(tx(), vec![prevout.clone()]).unwrap();
+        let mut one = [0u8; 32];
+        one[0] = 1;
+
+        // The transaction has no outputs, so there is no output matching the input
+        for sighash_type in [0x03, 0x83] {
+            assert_eq!(
+                cache.legacy_sighash(0, &prevout.script_pubkey, sighash_type).unwrap(),
+                Sighash::from(one)
+            );
+        }
+        assert_ne!(
+            cache.legacy_sighash(0, &prevout.script_pubkey, 0x01).unwrap(),
+            Sighash::from(one)
+        );
+        assert!(matches!(
+            cache.legacy_sighash(1, &prevout.script_pubkey, 0x03),
+            Err(SighashError::InvalidInputIndex { .. })
+        ));
+    }
+}
@@ -21,12 +21,16 @@
 
 use std::fmt::{self, Display, Formatter};
 use std::iter;
+use std::str::FromStr;
 
 use amplify::{ByteArray, Bytes32, Wrapper};
-use commit_verify::{DigestExt, Sha256};
+use commit_verify::Sha256;
 use secp256k1::{ecdsa, schnorr};
 
-use crate::{NonStandardValue, ScriptBytes, ScriptPubkey, WitnessScript, LIB_NAME_BITCOIN};
+use crate::{
+    NonStandardValue, ScriptBytes, ScriptPubkey, Sha256d, WPubkeyHash, WScriptHash, WitnessScript,
+    LIB_NAME_BITCOIN,
+};
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Display, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
@@ -58,6 +62,14 @@ pub struct SighashType {
 }
 
 impl SighashType {
+    /// `SIGHASH_ALL`: signs all inputs and outputs.
+    pub const ALL: Self = Self::all();
+    /// `SIGHASH_NONE`: signs all inputs and no outputs.
+    pub const NONE: Self = Self::none();
+    /// `SIGHASH_SINGLE`: signs all inputs and the output with the same index
+    /// as the signed input.
+    pub const SINGLE: Self = Self::single();
+
     pub const fn all() -> Self {
         SighashType {
             flag: SighashFlag::All,
@@ -129,6 +141,26 @@ impl SighashType {
         }
     }
 
+    /// Creates a [`SighashType`] from a sighash byte appended to a signature,
+    /// following the consensus rules (see [`Self::from_consensus_u32`]).
+    ///
+    /// Returns `None` for the zero byte. It can't be mapped to any sighash
+    /// type without loss: legacy and segwit v0 signatures with it commit to
+    /// the raw zero value, while in taproot it means `SIGHASH_DEFAULT`, which
+    /// must not be appended to a signature explicitly.
+    #[inline]
+    pub fn from_consensus_u8(n: u8) -> Option<SighashType> {
+        if n == 0 {
+            return None;
+        }
+        Some(Self::from_consensus_u32(n as u32))
+    }
+
+    /// Detects whether the `ANYONECANPAY` modifier is set, meaning that only
+    /// the signed input is committed to.
+    #[inline]
+    pub const fn anyone_can_pay(&self) -> bool { self.anyone_can_pay }
+
     /// Creates a [`SighashType`] from a raw `u32`.
     ///
     /// # Errors
@@ -182,12 +214,48 @@ impl Display for SighashType {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         Display::fmt(&self.flag, f)?;
         if self.anyone_can_pay {
-            f.write_str(" | ANYONECANPAY")?;
+            f.write_str("|ANYONECANPAY")?;
         }
         Ok(())
     }
 }
 
+/// Parses sighash type from its string representation, like `ALL` or
+/// `SINGLE|ANYONECANPAY`. Spaces around `|` are allowed, so the `SINGLE |
+/// ANYONECANPAY` form produced by the previous versions is accepted as well.
+impl FromStr for SighashType {
+    type Err = SighashTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (flag, anyone_can_pay) = match s.split_once('|') {
+            None => (s, false),
+            Some((flag, modifier)) if modifier.trim_start() == "ANYONECANPAY" => {
+                (flag.trim_end(), true)
+            }
+            Some(_) => return Err(SighashTypeParseError(s.to_owned())),
+        };
+        let flag = match flag {
+            "ALL" => SighashFlag::All,
+            "NONE" => SighashFlag::None,
+            "SINGLE" => SighashFlag::Single,
+            _ => return Err(SighashTypeParseError(s.to_owned())),
+        };
+        Ok(SighashType {
+            flag,
+            anyone_can_pay,
+        })
+    }
+}
+
+/// invalid sighash type '{0}'.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub struct SighashTypeParseError(pub String);
+
+/// Signature hash message for legacy and segwit v0 inputs, produced with
+/// double SHA256 and signed with ECDSA.
+///
+/// For taproot inputs see [`crate::TapSighash`].
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[wrapper(Index, RangeOps, AsSlice, BorrowSlice, Hex, Display, FromStr)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -210,11 +278,7 @@ impl From<Sighash> for secp256k1::Message {
 impl Sighash {
     pub fn engine() -> Sha256 { Sha256::default() }
 
-    pub fn from_engine(engine: Sha256) -> Self {
-        let mut engine2 = Sha256::default();
-        engine2.input_raw(&engine.finish());
-        Self(engine2.finish().into())
-    }
+    pub fn from_engine(engine: Sha256) -> Self { Self(Sha256d::from(engine).finish().into()) }
 }
 
 /// Type used for generating sighash in SegWit signing
@@ -229,10 +293,43 @@ impl ScriptCode {
     pub fn with_p2wpkh(script_pubkey: &ScriptPubkey) -> Self {
         let mut pubkey_hash = [0u8; 20];
         pubkey_hash.copy_from_slice(&script_pubkey[2..22]);
-        let script_code = ScriptPubkey::p2pkh(pubkey_hash);
+        Self::for_p2wpkh(&WPubkeyHash::from(pubkey_hash))
+    }
+
+    /// Constructs script code for a P2WPKH spending, which is the implied
+    /// `OP_DUP OP_HASH160 <pkh> OP_EQUALVERIFY OP_CHECKSIG` script.
+    pub fn for_p2wpkh(pkh: &WPubkeyHash) -> Self {
+        let script_code = ScriptPubkey::p2pkh(<[u8; 20]>::from(*pkh));
         ScriptCode(script_code.into_inner())
     }
 
+    /// Constructs script code for a P2WSH spending from the witness script.
+    #[inline]
+    pub fn for_p2wsh(witness_script: &WitnessScript) -> Self { Self::with_p2wsh(witness_script) }
+
+    /// Detects the script code for a segwit v0 spending from the type of the
+    /// spent output `script_pubkey`.
+    ///
+    /// For P2WSH outputs the `witness_script` must be provided and must match
+    /// the hash committed in the output. Returns `None` if the output is not a
+    /// segwit v0 one, or if the witness script is absent or doesn't match.
+    pub fn for_prevout(
+        script_pubkey: &ScriptPubkey,
+        witness_script: Option<&WitnessScript>,
+    ) -> Option<Self> {
+        if script_pubkey.is_p2wpkh() {
+            return Some(Self::with_p2wpkh(script_pubkey));
+        }
+        if !script_pubkey.is_p2wsh() {
+            return None;
+        }
+        let witness_script = witness_script?;
+        if <[u8; 32]>::from(WScriptHash::from(witness_script)) != script_pubkey[2..34] {
+            return None;
+        }
+        Some(Self::for_p2wsh(witness_script))
+    }
+
     pub fn with_p2sh_wsh(witness_script: &WitnessScript) -> Self {
         Self::with_p2wsh(witness_script)
     }
@@ -264,6 +361,10 @@ pub enum SigError {
     /// invalid BIP340 signature length ({0}).
     Bip340Encoding(usize),
 
+    /// BIP340 signature has an explicit `SIGHASH_DEFAULT` (0x00) sighash byte,
+    /// which must be omitted.
+    ExplicitSighashDefault,
+
     /// invalid BIP340 signature.
     InvalidSignature,
 }
@@ -317,12 +418,18 @@ impl LegacySig {
 pub struct Bip340Sig {
     /// The underlying ECDSA Signature
     pub sig: schnorr::Signature,
-    /// The corresponding hash type
+    /// The corresponding hash type, with `None` standing for BIP341
+    /// `SIGHASH_DEFAULT`.
+    ///
+    /// `SIGHASH_DEFAULT` signs the same data as `SIGHASH_ALL`, but results in
+    /// a different signature hash and is serialized without the trailing
+    /// sighash byte.
     pub sighash_type: Option<SighashType>,
 }
 
 impl Bip340Sig {
-    /// Constructs an ECDSA bitcoin signature for [`SighashType::All`].
+    /// Constructs a BIP340 signature for `SIGHASH_DEFAULT`, which is
+    /// serialized as 64 bytes.
     pub fn sighash_default(sig: schnorr::Signature) -> Self {
         Bip340Sig {
             sig,
@@ -330,12 +437,30 @@ impl Bip340Sig {
         }
     }
 
+    /// Constructs a BIP340 signature with an explicit sighash type, which is
+    /// serialized as 65 bytes.
+    pub fn with_sighash_type(sig: schnorr::Signature, sighash_type: SighashType) -> Self {
+        Bip340Sig {
+            sig,
+            sighash_type: Some(sighash_type),
+        }
+    }
+
+    /// Detects whether the signature uses `SIGHASH_DEFAULT`.
+    #[inline]
+    pub const fn is_sighash_default(&self) -> bool { self.sighash_type.is_none() }
+
     /// Deserializes from slice following the standardness rules for
     /// [`SighashType`].
+    ///
+    /// A 64-byte signature uses `SIGHASH_DEFAULT`; a 65-byte signature must
+    /// end with a sighash byte other than `SIGHASH_DEFAULT` (0x00), as
+    /// required by BIP341.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError> {
         let (hash_ty, sig) = match bytes.len() {
             0 => return Err(SigError::EmptySignature),
             64 => (None, bytes),
+            65 if bytes[64] == 0x00 => return Err(SigError::ExplicitSighashDefault),
             65 => (Some(bytes[64] as u32), &bytes[..64]),
             invalid => return Err(SigError::Bip340Encoding(invalid)),
         };
@@ -344,8 +469,16 @@ impl Bip340Sig {
         Ok(Bip340Sig { sig, sighash_type })
     }
 
-    /// Serializes an ECDSA signature (inner secp256k1 signature in DER format)
-    /// into `Vec`.
+    /// Parses a signature from a witness element, which must be either 64
+    /// bytes (`SIGHASH_DEFAULT`) or 65 bytes (with a standard sighash type in
+    /// the last byte).
+    ///
+    /// Same as [`Bip340Sig::from_bytes`].
+    #[inline]
+    pub fn from_slice(slice: &[u8]) -> Result<Self, SigError> { Self::from_bytes(slice) }
+
+    /// Serializes a BIP340 signature into `Vec`, appending the sighash byte
+    /// unless the signature uses `SIGHASH_DEFAULT`.
     // TODO: add support to serialize to a writer to SerializedSig
     pub fn to_vec(self) -> Vec<u8> {
         let mut ser = Vec::<u8>::with_capacity(65);
@@ -444,3 +577,109 @@ mod _strict_encode {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sighash_type() {
+        assert_eq!(SighashType::ALL, SighashType::all());
+        assert_eq!(SighashType::ALL.to_string(), "ALL");
+        assert_eq!(SighashType::single_anyone_can_pay().to_string(), "SINGLE|ANYONECANPAY");
+        for n in [0x01u8, 0x02, 0x03, 0x81, 0x82, 0x83] {
+            let ty = SighashType::from_consensus_u8(n).unwrap();
+            assert_eq!(ty.to_consensus_u8(), n);
+            assert_eq!(ty.anyone_can_pay(), n & 0x80 != 0);
+        }
+        assert_eq!(SighashType::from_consensus_u8(0x83).unwrap().flag, SighashFlag::Single);
+        assert_eq!(SighashType::from_consensus_u8(0x00), None);
+        assert_eq!(SighashType::from_consensus_u8(0x84), Some(SighashType::all_anyone_can_pay()));
+    }
+
+    #[test]
+    fn sighash_type_from_str() {
+        for ty in [
+            SighashType::all(),
+            SighashType::none(),
+            SighashType::single(),
+            SighashType::all_anyone_can_pay(),
+            SighashType::none_anyone_can_pay(),
+            SighashType::single_anyone_can_pay(),
+        ] {
+            assert_eq!(SighashType::from_str(&ty.to_string()), Ok(ty));
+        }
+        assert_eq!(
+            SighashType::from_str("NONE | ANYONECANPAY"),
+            Ok(SighashType::none_anyone_can_pay())
+        );
+        assert!(SighashType::from_str("ANYONECANPAY").is_err());
+        assert!(SighashType::from_str("ALL|").is_err());
+        assert!(SighashType::from_str("all").is_err());
+        assert!(SighashType::from_str("ALL|SINGLE").is_err());
+    }
+
+    #[test]
+    fn bip340_sighash_default() {
+        let sig = schnorr::Signature::from_slice(&[0x11; 64]).unwrap();
+
+        let default = Bip340Sig::sighash_default(sig);
+        assert!(default.is_sighash_default());
+        assert_eq!(default.to_vec().len(), 64);
+        assert_eq!(Bip340Sig::from_bytes(&default.to_vec()), Ok(default));
+
+        let all = Bip340Sig::with_sighash_type(sig, SighashType::ALL);
+        assert!(!all.is_sighash_default());
+        assert_eq!(all.to_vec().len(), 65);
+        assert_eq!(all.to_vec()[64], 0x01);
+        assert_eq!(Bip340Sig::from_bytes(&all.to_vec()), Ok(all));
+        assert_ne!(default, all);
+
+        let mut explicit_default = default.to_vec();
+        explicit_default.push(0x00);
+        assert_eq!(Bip340Sig::from_bytes(&explicit_default), Err(SigError::ExplicitSighashDefault));
+    }
+
+    #[test]
+    fn bip340_from_slice() {
+        let mut data = vec![0x11; 64];
+        assert_eq!(Bip340Sig::from_slice(&data).unwrap().sighash_type, None);
+        data.push(0x83);
+        assert_eq!(
+            Bip340Sig::from_slice(&data).unwrap().sighash_type,
+            Some(SighashType::single_anyone_can_pay())
+        );
+        *data.last_mut().unwrap() = 0x04;
+        assert!(matches!(Bip340Sig::from_slice(&data), Err(SigError::SighashType(_))));
+
+        assert_eq!(Bip340Sig::from_slice(&[]), Err(SigError::EmptySignature));
+        assert_eq!(Bip340Sig::from_slice(&[0x11; 63]), Err(SigError::Bip340Encoding(63)));
+        assert_eq!(Bip340Sig::from_slice(&[0x11; 66]), Err(SigError::Bip340Encoding(66)));
+    }
+
+    #[test]
+    fn script_code_segwit_v0() {
+        let pkh = WPubkeyHash::from([0xAB; 20]);
+        let script_code = ScriptCode::for_p2wpkh(&pkh);
+        let mut expected = vec![0x76, 0xA9, 0x14];
+        expected.extend([0xAB; 20]);
+        expected.extend([0x88, 0xAC]);
+        assert_eq!(script_code.as_slice(), expected.as_slice());
+
+        let spk = ScriptPubkey::p2wpkh(pkh);
+        assert_eq!(ScriptCode::for_prevout(&spk, None), Some(script_code.clone()));
+        assert_eq!(ScriptCode::with_p2wpkh(&spk), script_code);
+
+        let witness_script = WitnessScript::from_unsafe(vec![0x51]);
+        let spk = ScriptPubkey::p2wsh(WScriptHash::from(&witness_script));
+        assert_eq!(
+            ScriptCode::for_prevout(&spk, Some(&witness_script)),
+            Some(ScriptCode::for_p2wsh(&witness_script))
+        );
+        assert_eq!(ScriptCode::for_prevout(&spk, None), None);
+        let other = WitnessScript::from_unsafe(vec![0x52]);
+        assert_eq!(ScriptCode::for_prevout(&spk, Some(&other)), None);
+
+        assert_eq!(ScriptCode::for_prevout(&ScriptPubkey::p2pkh([0xAB; 20]), None), None);
+    }
+}
@@ -22,7 +22,7 @@
 #![allow(unused_braces)] // required due to strict dumb derivation and compiler bug
 
 use std::borrow::Borrow;
-use std::fmt::{self, Formatter, LowerHex, UpperHex};
+use std::fmt::{self, Display, Formatter, LowerHex, UpperHex};
 use std::ops::BitXor;
 use std::str::FromStr;
 use std::{cmp, io, slice, vec};
@@ -31,7 +31,7 @@ use amplify::confinement::Confined;
 use amplify::hex::FromHex;
 use amplify::{confinement, ByteArray, Bytes32, Wrapper};
 use commit_verify::{DigestExt, Sha256};
-use secp256k1::{Keypair, PublicKey, Scalar, XOnlyPublicKey};
+use secp256k1::{Keypair, PublicKey, Scalar, Secp256k1, Verification, XOnlyPublicKey};
 use strict_encoding::{
     DecodeError, ReadTuple, StrictDecode, StrictEncode, StrictProduct, StrictTuple, StrictType,
     TypeName, TypedRead, TypedWrite, WriteTuple,
@@ -79,6 +79,9 @@ impl<const LEN: usize> From<InvalidPubkey<LEN>> for DecodeError {
 pub struct XOnlyPk(XOnlyPublicKey);
 
 impl XOnlyPk {
+    /// Length of the serialized x-only public key, in bytes.
+    pub const LEN: usize = 32;
+
     fn dumb() -> Self { Self(XOnlyPublicKey::from_slice(&[1u8; 32]).unwrap()) }
 
     pub fn from_byte_array(data: [u8; 32]) -> Result<Self, InvalidPubkey<32>> {
@@ -140,7 +143,18 @@ impl FromStr for XOnlyPk {
 pub struct InternalKeypair(#[from] Keypair);
 
 impl InternalKeypair {
+    #[inline]
     pub fn to_output_keypair(&self, merkle_root: Option<TapNodeHash>) -> (Keypair, Parity) {
+        self.to_output_keypair_with_ctx(secp256k1::SECP256K1, merkle_root)
+    }
+
+    /// Computes output keypair using the provided secp256k1 context instead
+    /// of the global one.
+    pub fn to_output_keypair_with_ctx<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        merkle_root: Option<TapNodeHash>,
+    ) -> (Keypair, Parity) {
         let internal_pk = self.0.x_only_public_key().0;
         let mut engine = Sha256::from_tag(MIDSTATE_TAPTWEAK);
         // always hash the key
@@ -150,14 +164,9 @@ impl InternalKeypair {
         }
         let tweak =
             Scalar::from_be_bytes(engine.finish()).expect("hash value greater than curve order");
-        let pair = self.0.add_xonly_tweak(secp256k1::SECP256K1, &tweak).expect("hash collision");
+        let pair = self.0.add_xonly_tweak(secp, &tweak).expect("hash collision");
         let (outpput_key, tweaked_parity) = pair.x_only_public_key();
-        debug_assert!(internal_pk.tweak_add_check(
-            secp256k1::SECP256K1,
-            &outpput_key,
-            tweaked_parity,
-            tweak
-        ));
+        debug_assert!(internal_pk.tweak_add_check(secp, &outpput_key, tweaked_parity, tweak));
         (pair, tweaked_parity.into())
     }
 }
@@ -177,6 +186,29 @@ pub struct InternalPk(
 );
 
 impl InternalPk {
+    /// Length of the serialized internal key, in bytes.
+    pub const LEN: usize = XOnlyPk::LEN;
+
+    /// Serialized BIP-341 "nothing up my sleeve" (NUMS) point `H`, which has
+    /// no known discrete logarithm. Using it as an internal key provably
+    /// disables key path spending.
+    pub const NUMS: [u8; 32] = [
+        0x50, 0x92, 0x9b, 0x74, 0xc1, 0xa0, 0x49, 0x54, 0xb7, 0x8b, 0x4b, 0x60, 0x35, 0xe9, 0x7a,
+        0x5e, 0x07, 0x8a, 0x5a, 0x0f, 0x28, 0xec, 0x96, 0xd5, 0x47, 0xbf, 0xee, 0x9a, 0xce, 0x80,
+        0x3a, 0xc0,
+    ];
+
+    /// Constructs internal key from the BIP-341 NUMS point (see
+    /// [`InternalPk::NUMS`]).
+    pub fn nums() -> Self {
+        Self::from_byte_array(Self::NUMS).expect("BIP-341 NUMS point is a valid x-only key")
+    }
+
+    /// Detects whether the key is the BIP-341 NUMS point (see
+    /// [`InternalPk::NUMS`]).
+    #[inline]
+    pub fn is_nums(&self) -> bool { self.to_byte_array() == Self::NUMS }
+
     #[inline]
     pub fn from_unchecked(pk: XOnlyPk) -> Self { Self(pk) }
 
@@ -196,23 +228,80 @@ impl InternalPk {
     #[inline]
     pub fn to_xonly_pk(&self) -> XOnlyPk { self.0 }
 
+    #[inline]
     pub fn to_output_pk(&self, merkle_root: Option<TapNodeHash>) -> (OutputPk, Parity) {
+        self.to_output_pk_with_ctx(secp256k1::SECP256K1, merkle_root)
+    }
+
+    /// Computes output key using the provided secp256k1 context instead of
+    /// the global one.
+    pub fn to_output_pk_with_ctx<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        merkle_root: Option<TapNodeHash>,
+    ) -> (OutputPk, Parity) {
         let mut engine = Sha256::from_tag(MIDSTATE_TAPTWEAK);
         // always hash the key
         engine.input_raw(&self.0.serialize());
         if let Some(merkle_root) = merkle_root {
             engine.input_raw(merkle_root.into_tap_hash().as_ref());
         }
+        self.tweak(secp, engine)
+    }
+
+    /// Computes output key for the key path-only spending (i.e. without a
+    /// script tree), as defined in BIP-341 and BIP-86.
+    ///
+    /// The function is equivalent to `to_output_pk(None)`, but skips the
+    /// optional merkle root handling and performs no heap allocations, making
+    /// it suitable for a use in hot loops deriving key-only taproot outputs.
+    pub fn to_output_pk_key_only(&self) -> (OutputPk, Parity) {
+        let mut engine = Sha256::from_tag(MIDSTATE_TAPTWEAK);
+        engine.input_raw(&self.0.serialize());
+        self.tweak(secp256k1::SECP256K1, engine)
+    }
+
+    /// Computes output keys for a batch of internal keys, each tweaked with the
+    /// merkle root at the same position in `merkle_roots`.
+    ///
+    /// The tagged hash midstate is computed once and reused for all the keys,
+    /// and all the tweaks are done with the global secp256k1 context. The
+    /// elliptic curve tweak-add operation is still performed for each key
+    /// independently, since secp256k1 doesn't provide a batched version of it;
+    /// thus the benefit is limited to the saved hashing setup.
+    ///
+    /// # Panics
+    ///
+    /// If the lengths of `internal` and `merkle_roots` don't match.
+    pub fn to_output_pks(
+        internal: &[InternalPk],
+        merkle_roots: &[Option<TapNodeHash>],
+    ) -> Vec<(OutputPk, Parity)> {
+        assert_eq!(
+            internal.len(),
+            merkle_roots.len(),
+            "number of internal keys doesn't match the number of merkle roots"
+        );
+        let midstate = Sha256::from_tag(MIDSTATE_TAPTWEAK);
+        internal
+            .iter()
+            .zip(merkle_roots)
+            .map(|(pk, merkle_root)| {
+                let mut engine = midstate.clone();
+                engine.input_raw(&pk.0.serialize());
+                if let Some(merkle_root) = merkle_root {
+                    engine.input_raw(merkle_root.as_ref());
+                }
+                pk.tweak(secp256k1::SECP256K1, engine)
+            })
+            .collect()
+    }
+
+    fn tweak<C: Verification>(&self, secp: &Secp256k1<C>, engine: Sha256) -> (OutputPk, Parity) {
         let tweak =
             Scalar::from_be_bytes(engine.finish()).expect("hash value greater than curve order");
-        let (output_key, tweaked_parity) =
-            self.0.add_tweak(secp256k1::SECP256K1, &tweak).expect("hash collision");
-        debug_assert!(self.tweak_add_check(
-            secp256k1::SECP256K1,
-            &output_key,
-            tweaked_parity,
-            tweak
-        ));
+        let (output_key, tweaked_parity) = self.0.add_tweak(secp, &tweak).expect("hash collision");
+        debug_assert!(self.tweak_add_check(secp, &output_key, tweaked_parity, tweak));
         (OutputPk(XOnlyPk(output_key)), tweaked_parity.into())
     }
 }
@@ -233,6 +322,9 @@ impl From<InternalPk> for [u8; 32] {
 pub struct OutputPk(XOnlyPk);
 
 impl OutputPk {
+    /// Length of the serialized output key, in bytes.
+    pub const LEN: usize = XOnlyPk::LEN;
+
     #[inline]
     pub fn from_unchecked(pk: XOnlyPk) -> Self { Self(pk) }
 
@@ -264,6 +356,10 @@ pub trait IntoTapHash {
     fn into_tap_hash(self) -> TapNodeHash;
 }
 
+/// BIP341 signature hash message for taproot inputs (both key- and
+/// script-path spends), signed with BIP340 Schnorr signatures.
+///
+/// For legacy and segwit v0 inputs see [`crate::Sighash`].
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[wrapper(Index, RangeOps, AsSlice, BorrowSlice, Hex, Display, FromStr)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -361,10 +457,57 @@ pub struct TapNodeHash(
     Bytes32,
 );
 
+impl TapNodeHash {
+    /// Length of the tap node hash, in bytes.
+    pub const LEN: usize = 32;
+}
+
 impl IntoTapHash for TapNodeHash {
     fn into_tap_hash(self) -> TapNodeHash { self }
 }
 
+/// Node of a taproot script tree, keeping information whether the node hash
+/// was produced by a leaf or a branch.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub enum TapNode {
+    /// Node is a script leaf.
+    #[from]
+    Leaf(TapLeafHash),
+    /// Node is a branch combining two child nodes.
+    #[from]
+    Branch(TapBranchHash),
+}
+
+impl TapNode {
+    /// Constructs a branch node from two child nodes.
+    pub fn with_nodes(node1: impl IntoTapHash, node2: impl IntoTapHash) -> Self {
+        TapNode::Branch(TapBranchHash::with_nodes(node1.into_tap_hash(), node2.into_tap_hash()))
+    }
+
+    /// Detects whether the node is a script leaf.
+    pub fn is_leaf(&self) -> bool { matches!(self, TapNode::Leaf(_)) }
+
+    /// Detects whether the node is a branch.
+    pub fn is_branch(&self) -> bool { matches!(self, TapNode::Branch(_)) }
+
+    /// Returns the node hash, which is the same for leaves and branches.
+    pub fn to_node_hash(&self) -> TapNodeHash {
+        match *self {
+            TapNode::Leaf(hash) => hash.into_tap_hash(),
+            TapNode::Branch(hash) => hash.into_tap_hash(),
+        }
+    }
+}
+
+impl IntoTapHash for TapNode {
+    fn into_tap_hash(self) -> TapNodeHash { self.to_node_hash() }
+}
+
+impl From<TapNode> for TapNodeHash {
+    fn from(node: TapNode) -> Self { node.to_node_hash() }
+}
+
 #[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Default)]
 #[wrapper(Deref)]
 #[wrapper_mut(DerefMut)]
@@ -387,6 +530,20 @@ impl<'a> IntoIterator for &'a TapMerklePath {
     fn into_iter(self) -> Self::IntoIter { self.0.iter() }
 }
 
+/// Displays merkle path as a colon-separated list of branch hashes, starting
+/// from the leaf level.
+impl Display for TapMerklePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, hash) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(":")?;
+            }
+            Display::fmt(hash, f)?;
+        }
+        Ok(())
+    }
+}
+
 impl TapMerklePath {
     /// Tries to construct a confinement over a collection. Fails if the number
     /// of items in the collection exceeds one of the confinement bounds.
@@ -419,6 +576,48 @@ pub const TAPROOT_LEAF_TAPSCRIPT: u8 = 0xc0;
 // https://github.com/bitcoin/bitcoin/blob/e826b22da252e0599c61d21c98ff89f366b3120f/src/script/interpreter.h#L225
 pub const TAPROOT_LEAF_MASK: u8 = 0xfe;
 
+/// Maximum weight of a transaction relayed under the standard policy
+/// (`MAX_STANDARD_TX_WEIGHT` in Bitcoin Core).
+pub const MAX_STANDARD_TX_WEIGHT: usize = 400_000;
+
+/// Checks whether a tapscript of the given length fits into a standard
+/// transaction having a single input and a single output.
+fn is_standard_tap_script_size(len: usize) -> bool {
+    // Version, lock time, input and output counts, single input with an empty
+    // signature script and a P2TR output, counted with the witness scale
+    // factor of 4, plus segwit marker and flag
+    const MIN_TX_WEIGHT: usize = (4 + 4 + 1 + 1 + 41 + 43) * 4 + 2;
+    // Number of witness elements and length prefixes of the script and the
+    // control block (which is at least 33 bytes long)
+    const MIN_WITNESS_OVERHEAD: usize = 1 + 5 + 1 + 33;
+    len <= MAX_STANDARD_TX_WEIGHT - MIN_TX_WEIGHT - MIN_WITNESS_OVERHEAD
+}
+
+/// Checks whether the opcode is one of `OP_SUCCESSx` opcodes defined in
+/// BIP-342.
+fn is_op_success(opcode: u8) -> bool {
+    matches!(
+        opcode,
+        80 | 98 | 126..=129 | 131..=134 | 137..=138 | 141..=142 | 149..=153 | 187..=254
+    )
+}
+
+/// Reasons why spending a taproot leaf script is non-standard.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum LeafStandardnessError {
+    /// leaf version {0:#04x} is reserved for future upgrades; spending it is
+    /// non-standard.
+    UpgradableLeafVer(u8),
+
+    /// leaf script of {0} bytes can't fit into a standard transaction.
+    OversizedScript(usize),
+
+    /// leaf script contains `OP_SUCCESS{0}` opcode; spending it is
+    /// non-standard.
+    OpSuccess(u8),
+}
+
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Display, Error)]
 #[display(doc_comments)]
 /// invalid taproot leaf version {0}.
@@ -579,6 +778,125 @@ impl LeafScript {
 
     #[inline]
     pub fn tap_leaf_hash(&self) -> TapLeafHash { TapLeafHash::with_leaf_script(self) }
+
+    /// Checks whether a transaction spending the leaf script will be relayed
+    /// under the standard policy.
+    ///
+    /// Detects spending of future leaf versions, scripts not fitting into a
+    /// standard transaction (see [`TapScript::is_standard_size`]) and scripts
+    /// containing `OP_SUCCESSx` opcodes. Malformed scripts are not reported,
+    /// since they fail consensus validation anyway.
+    ///
+    /// # Errors
+    ///
+    /// With the first detected reason for the script to be non-standard.
+    pub fn standardness_check(&self) -> Result<(), LeafStandardnessError> {
+        if let LeafVer::Future(version) = self.version {
+            return Err(LeafStandardnessError::UpgradableLeafVer(version.to_consensus()));
+        }
+        if !is_standard_tap_script_size(self.script.len()) {
+            return Err(LeafStandardnessError::OversizedScript(self.script.len()));
+        }
+        match self
+            .script
+            .instructions()
+            .map_while(Result::ok)
+            .map(|instruction| instruction.opcode())
+            .find(|opcode| is_op_success(*opcode))
+        {
+            Some(opcode) => Err(LeafStandardnessError::OpSuccess(opcode)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Errors in the structure of a taproot script tree.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TapTreeError {
+    /// taproot script tree must contain at least one leaf.
+    Empty,
+
+    /// taproot script tree leaf depth {0} exceeds BIP-341 consensus limit of
+    /// 128.
+    TooDeep(u8),
+
+    /// taproot script tree leaves depths do not form a complete binary tree.
+    Incomplete,
+}
+
+/// Leaf script of a taproot script tree together with its depth in the tree.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct TapTreeLeaf {
+    pub depth: u8,
+    pub script: LeafScript,
+}
+
+/// Taproot script tree, represented by the list of its leaves in the
+/// depth-first search order, as used in PSBT `PSBT_IN_TAP_TREE` and
+/// `PSBT_OUT_TAP_TREE` fields (see BIP-371).
+///
+/// The structure is guaranteed to represent a complete binary tree.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct TapTree(Vec<TapTreeLeaf>);
+
+impl<'a> IntoIterator for &'a TapTree {
+    type Item = &'a TapTreeLeaf;
+    type IntoIter = slice::Iter<'a, TapTreeLeaf>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+}
+
+impl TapTree {
+    /// Constructs taproot script tree from a list of leaves in the depth-first
+    /// search order, checking that they form a complete binary tree.
+    pub fn from_leaves(leaves: Vec<TapTreeLeaf>) -> Result<Self, TapTreeError> {
+        let tree = TapTree(leaves);
+        tree.compute_merkle_root()?;
+        Ok(tree)
+    }
+
+    #[inline]
+    pub fn leaves(&self) -> slice::Iter<'_, TapTreeLeaf> { self.0.iter() }
+
+    #[inline]
+    pub fn into_leaves(self) -> Vec<TapTreeLeaf> { self.0 }
+
+    /// Computes merkle root of the script tree.
+    pub fn merkle_root(&self) -> TapNodeHash {
+        self.compute_merkle_root().expect("TapTree is always a complete binary tree")
+    }
+
+    fn compute_merkle_root(&self) -> Result<TapNodeHash, TapTreeError> {
+        let mut stack = Vec::<(u8, TapNodeHash)>::with_capacity(self.0.len());
+        for leaf in &self.0 {
+            if leaf.depth > 128 {
+                return Err(TapTreeError::TooDeep(leaf.depth));
+            }
+            if matches!(stack.last(), Some((0, _))) {
+                return Err(TapTreeError::Incomplete);
+            }
+            let mut node = (leaf.depth, leaf.script.tap_leaf_hash().into_tap_hash());
+            while let Some((depth, sibling)) = stack.last().copied() {
+                if depth != node.0 {
+                    break;
+                }
+                if depth == 0 {
+                    return Err(TapTreeError::Incomplete);
+                }
+                stack.pop();
+                node = (depth - 1, TapBranchHash::with_nodes(sibling, node.1).into_tap_hash());
+            }
+            stack.push(node);
+        }
+        match stack.as_slice() {
+            [] => Err(TapTreeError::Empty),
+            [(0, root)] => Ok(*root),
+            _ => Err(TapTreeError::Incomplete),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
@@ -648,6 +966,18 @@ impl TapScript {
     #[inline]
     pub fn tap_leaf_hash(&self) -> TapLeafHash { TapLeafHash::with_tap_script(self) }
 
+    /// Checks whether the script can be revealed in a standard transaction.
+    ///
+    /// Unlike legacy and segwit v0 scripts, tapscripts are not subject to the
+    /// 10000-byte script size limit (BIP-342). Their size is restricted only
+    /// by the requirement for the witness revealing the script, together
+    /// with the control block and the rest of the spending transaction, to
+    /// fit [`MAX_STANDARD_TX_WEIGHT`]. Since witness bytes have weight of 1,
+    /// the method checks that the script and the smallest possible control
+    /// block leave space for the non-witness data of a minimal transaction.
+    #[inline]
+    pub fn is_standard_size(&self) -> bool { is_standard_tap_script_size(self.len()) }
+
     /// Adds a single opcode to the script.
     #[inline]
     pub fn push_opcode(&mut self, op_code: TapCode) { self.0.push(op_code as u8); }
@@ -678,9 +1008,7 @@ impl ScriptPubkey {
         Self::with_witness_program_unchecked(WitnessVer::V1, &output_key.serialize())
     }
 
-    pub fn is_p2tr(&self) -> bool {
-        self.len() == 34 && self[0] == WitnessVer::V1.op_code() as u8 && self[1] == OP_PUSHBYTES_32
-    }
+    pub fn is_p2tr(&self) -> bool { self.as_script_ref().is_p2tr() }
 }
 
 /// invalid parity value {0} - must be 0 or 1
@@ -762,6 +1090,18 @@ pub struct ControlBlock {
     pub merkle_branch: TapMerklePath,
 }
 
+/// Displays control block as a space-separated leaf version (in hex), output
+/// key parity, internal key and (if not empty) the merkle path.
+impl Display for ControlBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#04x} {} {}", self.leaf_version, self.output_key_parity, self.internal_pk)?;
+        if !self.merkle_branch.is_empty() {
+            write!(f, " {}", self.merkle_branch)?;
+        }
+        Ok(())
+    }
+}
+
 impl ControlBlock {
     #[inline]
     pub fn with(
@@ -777,6 +1117,24 @@ impl ControlBlock {
             merkle_branch,
         }
     }
+
+    /// Constructs control block for a script path spend from an output with
+    /// the given `merkle_root`, computing the output key parity from the
+    /// internal key and the merkle root.
+    pub fn with_computed_parity(
+        leaf_version: LeafVer,
+        internal_pk: InternalPk,
+        merkle_branch: TapMerklePath,
+        merkle_root: impl IntoTapHash,
+    ) -> Self {
+        let (_, output_key_parity) = internal_pk.to_output_pk(Some(merkle_root.into_tap_hash()));
+        ControlBlock {
+            leaf_version,
+            output_key_parity,
+            internal_pk,
+            merkle_branch,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
@@ -861,3 +1219,218 @@ mod _serde {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nums() {
+        let nums = InternalPk::nums();
+        assert!(nums.is_nums());
+        assert_eq!(
+            nums.to_string(),
+            "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0"
+        );
+    }
+
+    #[test]
+    fn leaf_standardness() {
+        let script = TapScript::from_unsafe(vec![OP_PUSHBYTES_1, 0x01, OP_CHECKSIG]);
+        assert!(script.is_standard_size());
+        assert_eq!(LeafScript::from_tap_script(script).standardness_check(), Ok(()));
+
+        // Tapscripts are not limited by the legacy 10000-byte limit
+        let script = TapScript::from_unsafe(vec![OP_NOP; 10_001]);
+        assert!(script.is_standard_size());
+        assert_eq!(LeafScript::from_tap_script(script).standardness_check(), Ok(()));
+
+        let script = TapScript::from_unsafe(vec![OP_NOP; MAX_STANDARD_TX_WEIGHT]);
+        assert!(!script.is_standard_size());
+        assert_eq!(
+            LeafScript::from_tap_script(script).standardness_check(),
+            Err(LeafStandardnessError::OversizedScript(MAX_STANDARD_TX_WEIGHT))
+        );
+
+        // OP_SUCCESS80, but not inside a push
+        let script = TapScript::from_unsafe(vec![OP_PUSHBYTES_1, 80, OP_DROP, 80]);
+        assert_eq!(
+            LeafScript::from_tap_script(script).standardness_check(),
+            Err(LeafStandardnessError::OpSuccess(80))
+        );
+
+        let leaf =
+            LeafScript::with_bytes(LeafVer::from_consensus_u8(0xc2).unwrap(), vec![]).unwrap();
+        assert_eq!(leaf.standardness_check(), Err(LeafStandardnessError::UpgradableLeafVer(0xc2)));
+    }
+
+    fn tagged_leaf_hash(preimage: &[u8]) -> [u8; 32] {
+        let mut tag = Sha256::default();
+        tag.input_raw(b"TapLeaf");
+        let tag = tag.finish();
+        let mut engine = Sha256::default();
+        engine.input_raw(&tag);
+        engine.input_raw(&tag);
+        engine.input_raw(preimage);
+        engine.finish()
+    }
+
+    #[test]
+    fn tap_leaf_hash_bip341() {
+        // BIP341 wallet test vector for a single-leaf script tree
+        let script = TapScript::from_unsafe(
+            Vec::<u8>::from_hex(
+                "20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac",
+            )
+            .unwrap(),
+        );
+        let leaf_hash = TapLeafHash::with_tap_script(&script);
+        assert_eq!(
+            leaf_hash.to_string(),
+            "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"
+        );
+        assert_eq!(leaf_hash, TapLeafHash::with_leaf_script(&LeafScript::from_tap_script(script)));
+
+        let internal_pk = InternalPk::from_str(
+            "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+        )
+        .unwrap();
+        let (output_pk, _) = internal_pk.to_output_pk(Some(leaf_hash.into_tap_hash()));
+        assert_eq!(
+            output_pk.to_string(),
+            "147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3"
+        );
+    }
+
+    #[test]
+    fn tap_leaf_hash_compact_size() {
+        let short = vec![OP_PUSHNUM_1; 0xfc];
+        let mut preimage = vec![0xc0, 0xfc];
+        preimage.extend(&short);
+        assert_eq!(
+            TapLeafHash::with_tap_script(&TapScript::from_unsafe(short)).to_byte_array(),
+            tagged_leaf_hash(&preimage)
+        );
+
+        let long = vec![OP_PUSHNUM_1; 300];
+        let mut preimage = vec![0xc0, 0xfd, 0x2c, 0x01];
+        preimage.extend(&long);
+        assert_eq!(
+            TapLeafHash::with_tap_script(&TapScript::from_unsafe(long)).to_byte_array(),
+            tagged_leaf_hash(&preimage)
+        );
+
+        let script = vec![OP_PUSHNUM_1; 3];
+        let leaf_script =
+            LeafScript::with_bytes(LeafVer::from_consensus_u8(0xc2).unwrap(), script).unwrap();
+        assert_eq!(
+            TapLeafHash::with_leaf_script(&leaf_script).to_byte_array(),
+            tagged_leaf_hash(&[0xc2, 3, OP_PUSHNUM_1, OP_PUSHNUM_1, OP_PUSHNUM_1])
+        );
+    }
+
+    #[test]
+    fn tap_node() {
+        let leaf1 = TapLeafHash::with_tap_script(&TapScript::from_unsafe(vec![OP_PUSHNUM_1]));
+        let leaf2 = TapLeafHash::with_tap_script(&TapScript::from_unsafe(vec![OP_PUSHNUM_2]));
+        let node = TapNode::from(leaf1);
+        assert!(node.is_leaf());
+        assert_eq!(node.to_node_hash(), TapNodeHash::from(leaf1));
+
+        let branch = TapNode::with_nodes(leaf1, TapNode::from(leaf2));
+        assert!(branch.is_branch());
+        assert_eq!(branch, TapNode::Branch(TapBranchHash::with_nodes(leaf2.into(), leaf1.into())));
+        assert_eq!(TapNodeHash::from(branch), branch.into_tap_hash());
+    }
+
+    #[test]
+    fn control_block_parity() {
+        let internal_pk = InternalPk::from_str(
+            "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+        )
+        .unwrap();
+        for seed in 0u8..8 {
+            let merkle_root = TapNodeHash::from([seed; 32]);
+            let (_, parity) = internal_pk.to_output_pk(Some(merkle_root));
+            let control_block = ControlBlock::with_computed_parity(
+                LeafVer::TapScript,
+                internal_pk,
+                none!(),
+                merkle_root,
+            );
+            assert_eq!(control_block.output_key_parity, parity);
+            assert_eq!(
+                control_block,
+                ControlBlock::with(LeafVer::TapScript, internal_pk, parity, none!())
+            );
+        }
+    }
+
+    #[test]
+    fn output_pk_key_only() {
+        let internal_pk = InternalPk::from_str(
+            "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+        )
+        .unwrap();
+        assert_eq!(internal_pk.to_output_pk_key_only(), internal_pk.to_output_pk(None));
+        assert_eq!(
+            internal_pk.to_output_pk_key_only().0.to_string(),
+            "a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c"
+        );
+    }
+
+    #[test]
+    fn output_pks_batch() {
+        let internal = [
+            InternalPk::from_str(
+                "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+            )
+            .unwrap(),
+            InternalPk::nums(),
+            InternalPk::from_str(
+                "c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3",
+            )
+            .unwrap(),
+        ];
+        let merkle_roots =
+            [None, Some(TapNodeHash::from([7u8; 32])), Some(TapNodeHash::from([8u8; 32]))];
+        let batch = InternalPk::to_output_pks(&internal, &merkle_roots);
+        let single = internal
+            .iter()
+            .zip(merkle_roots)
+            .map(|(pk, merkle_root)| pk.to_output_pk(merkle_root))
+            .collect::<Vec<_>>();
+        assert_eq!(batch, single);
+        assert!(InternalPk::to_output_pks(&[], &[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "number of internal keys")]
+    fn output_pks_batch_len_mismatch() { InternalPk::to_output_pks(&[InternalPk::nums()], &[]); }
+
+    #[test]
+    fn control_block_display() {
+        let path = TapMerklePath::try_from(vec![
+            TapBranchHash::from([0x11u8; 32]),
+            TapBranchHash::from([0x22u8; 32]),
+        ])
+        .unwrap();
+        assert_eq!(path.to_string(), format!("{}:{}", "11".repeat(32), "22".repeat(32)));
+        let mut cb =
+            ControlBlock::with(LeafVer::TapScript, InternalPk::nums(), Parity::Odd, none!());
+        assert_eq!(cb.to_string(), format!("0xc0 odd {}", InternalPk::nums()));
+        cb.merkle_branch = path.clone();
+        assert_eq!(cb.to_string(), format!("0xc0 odd {} {path}", InternalPk::nums()));
+    }
+
+    #[test]
+    fn output_pk_with_ctx() {
+        let secp = Secp256k1::verification_only();
+        let internal_pk = InternalPk::nums();
+        let merkle_root = Some(TapNodeHash::from([7u8; 32]));
+        assert_eq!(
+            internal_pk.to_output_pk_with_ctx(&secp, merkle_root),
+            internal_pk.to_output_pk(merkle_root)
+        );
+    }
+}
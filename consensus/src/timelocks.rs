@@ -52,7 +52,7 @@ pub const SEQ_NO_CSV_TYPE_MASK: u32 = 0x00400000;
 #[display("invalid timelock value {0}")]
 pub struct InvalidTimelock(pub u32);
 
-#[derive(Debug, Clone, PartialEq, Eq, From, Display)]
+#[derive(Debug, Clone, PartialEq, Eq, From, Display, Error)]
 #[display(doc_comments)]
 pub enum TimelockParseError {
     /// invalid number in time lock descriptor
@@ -149,10 +149,18 @@ impl LockTime {
 /// Value for a transaction `nTimeLock` field which is guaranteed to represent a
 /// UNIX timestamp which is always either 0 or a greater than or equal to
 /// 500000000.
+///
+/// Can be parsed from a string either as a plain number or as `time(<no>)`
+/// descriptor; values violating the bounds produce
+/// [`TimelockParseError::InvalidTimestamp`].
 #[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Hash, Debug, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "u32", into = "u32")
+)]
 pub struct LockTimestamp(u32);
 
 impl From<LockTimestamp> for u32 {
@@ -210,7 +218,7 @@ impl LockTimestamp {
 
     #[inline]
     pub const fn try_from_consensus_u32(lock_time: u32) -> Result<Self, InvalidTimelock> {
-        if !LockTime::from_consensus_u32(lock_time).is_time_based() {
+        if lock_time != 0 && !LockTime::from_consensus_u32(lock_time).is_time_based() {
             return Err(InvalidTimelock(lock_time));
         }
         Ok(Self(lock_time))
@@ -253,6 +261,9 @@ impl FromStr for LockTimestamp {
         } else if s.starts_with("time(") && s.ends_with(')') {
             let no = s[5..].trim_end_matches(')').parse()?;
             LockTimestamp::try_from(no).map_err(|_| TimelockParseError::InvalidTimestamp(no))
+        } else if s.bytes().all(|c| c.is_ascii_digit()) {
+            let no = s.parse()?;
+            LockTimestamp::try_from(no).map_err(|_| TimelockParseError::InvalidTimestamp(no))
         } else {
             Err(TimelockParseError::InvalidDescriptor(s))
         }
@@ -261,10 +272,18 @@ impl FromStr for LockTimestamp {
 
 /// Value for a transaction `nTimeLock` field which is guaranteed to represent a
 /// block height number which is always less than 500000000.
+///
+/// Can be parsed from a string either as a plain number or as `height(<no>)`
+/// descriptor; values violating the bounds produce
+/// [`TimelockParseError::InvalidHeight`].
 #[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Hash, Debug, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "u32", into = "u32")
+)]
 pub struct LockHeight(u32);
 
 impl From<LockHeight> for u32 {
@@ -357,6 +376,9 @@ impl FromStr for LockHeight {
         } else if s.starts_with("height(") && s.ends_with(')') {
             let no = s[7..].trim_end_matches(')').parse()?;
             LockHeight::try_from(no).map_err(|_| TimelockParseError::InvalidHeight(no))
+        } else if s.bytes().all(|c| c.is_ascii_digit()) {
+            let no = s.parse()?;
+            LockHeight::try_from(no).map_err(|_| TimelockParseError::InvalidHeight(no))
         } else {
             Err(TimelockParseError::InvalidDescriptor(s))
         }
@@ -372,6 +394,14 @@ pub struct SeqNo(u32);
 impl SeqNo {
     pub const ZERO: SeqNo = SeqNo(0);
 
+    /// Sequence number of a final input, which disables both relative time
+    /// locks and absolute lock time of the transaction.
+    pub const FINAL: SeqNo = SeqNo(0xFFFF_FFFF);
+
+    /// Maximal sequence number which does not signal opt-in replace-by-fee
+    /// (BIP-125) while still enabling the transaction lock time.
+    pub const MAX_NON_RBF: SeqNo = SeqNo(0xFFFF_FFFE);
+
     #[inline]
     pub const fn from_consensus_u32(lock_time: u32) -> Self { SeqNo(lock_time) }
 
@@ -402,6 +432,16 @@ impl SeqNo {
     }
 
     pub const fn is_timelock(self) -> bool { self.0 & SEQ_NO_CSV_DISABLE_MASK > 1 }
+
+    /// Detects whether the input is final, i.e. has sequence number equal to
+    /// [`SeqNo::FINAL`].
+    #[inline]
+    pub const fn is_final(self) -> bool { self.0 == Self::FINAL.0 }
+
+    /// Detects whether the sequence number signals opt-in replace-by-fee
+    /// according to BIP-125 (i.e. it is less than [`SeqNo::MAX_NON_RBF`]).
+    #[inline]
+    pub const fn is_rbf(self) -> bool { self.0 < Self::MAX_NON_RBF.0 }
 }
 
 /// Time lock interval describing both relative (OP_CHECKSEQUENCEVERIFY) and
@@ -423,3 +463,52 @@ pub enum TimeLockInterval {
 impl Default for TimeLockInterval {
     fn default() -> Self { TimeLockInterval::Height(default!()) }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lock_height_from_str() {
+        assert_eq!(LockHeight::from_str("0").unwrap(), LockHeight::anytime());
+        assert_eq!(LockHeight::from_str("840000").unwrap().to_consensus_u32(), 840000);
+        assert_eq!(LockHeight::from_str("height(840000)").unwrap().to_consensus_u32(), 840000);
+        assert_eq!(
+            LockHeight::from_str("499999999").unwrap().to_consensus_u32(),
+            LOCKTIME_THRESHOLD - 1
+        );
+        assert_eq!(
+            LockHeight::from_str("500000000"),
+            Err(TimelockParseError::InvalidHeight(LOCKTIME_THRESHOLD))
+        );
+        assert!(matches!(
+            LockHeight::from_str("-1"),
+            Err(TimelockParseError::InvalidDescriptor(_))
+        ));
+        assert!(matches!(
+            LockHeight::from_str("4294967296"),
+            Err(TimelockParseError::InvalidNumber(_))
+        ));
+    }
+
+    #[test]
+    fn lock_timestamp_from_str() {
+        assert_eq!(LockTimestamp::from_str("none").unwrap(), LockTimestamp::anytime());
+        assert_eq!(
+            LockTimestamp::from_str("500000000").unwrap().to_consensus_u32(),
+            LOCKTIME_THRESHOLD
+        );
+        assert_eq!(
+            LockTimestamp::from_str("time(1710668837)").unwrap().to_consensus_u32(),
+            1710668837
+        );
+        assert_eq!(
+            LockTimestamp::from_str("499999999"),
+            Err(TimelockParseError::InvalidTimestamp(LOCKTIME_THRESHOLD - 1))
+        );
+        assert_eq!(
+            LockTimestamp::try_from(LockTimestamp::anytime().to_consensus_u32()),
+            Ok(LockTimestamp::anytime())
+        );
+    }
+}
@@ -22,25 +22,38 @@
 use core::slice;
 use std::fmt::{self, Debug, Display, Formatter, LowerHex};
 use std::iter::Sum;
+use std::mem;
 use std::num::ParseIntError;
 use std::ops::{Div, Rem};
 use std::str::FromStr;
 
-use amplify::hex::{self, FromHex, ToHex};
-use amplify::{ByteArray, Bytes32StrRev, Wrapper};
-use commit_verify::{DigestExt, Sha256};
+use amplify::{confinement, hex, ByteArray, Bytes32StrRev, Wrapper};
 
 use crate::{
     ConsensusDecode, ConsensusDecodeError, ConsensusEncode, LockTime, NonStandardValue,
-    ScriptPubkey, SeqNo, SigScript, VarIntArray, Witness, Wtxid, LIB_NAME_BITCOIN,
+    ScriptError, ScriptPubkey, SeqNo, Sha256d, SigScript, VarIntArray, Witness, Wtxid,
+    LIB_NAME_BITCOIN, MAX_OP_RETURN_DATA,
 };
 
+/// Transaction id.
+///
+/// The id is stored in the internal byte order, in which it is produced by the
+/// hash function and serialized in transactions (see
+/// [`Txid::to_consensus_bytes`]). String representations (`Display`, hex and
+/// serde) use the reversed byte order, matching block explorers and Bitcoin
+/// Core RPC (see [`Txid::to_display_bytes`]).
+///
+/// Ordering of the ids is lexicographic over the consensus (internal) byte
+/// order, i.e. it matches the ordering of [`Txid::to_consensus_bytes`] and not
+/// the ordering of the string representation. This is guaranteed to remain
+/// stable, so the ids can be used as keys in ordered collections which are
+/// deterministically serialized.
 #[derive(Wrapper, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, From)]
 #[wrapper(AsSlice)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
-#[wrapper(BorrowSlice, Index, RangeOps, Debug, Hex, Display, FromStr)]
+#[wrapper(BorrowSlice, Index, RangeOps, Debug, Hex, Display)]
 // all-zeros used in coinbase
 pub struct Txid(
     #[from]
@@ -57,6 +70,46 @@ impl Txid {
     pub const fn coinbase() -> Self { Self(Bytes32StrRev::zero()) }
     #[inline]
     pub fn is_coinbase(&self) -> bool { self.to_byte_array() == [0u8; 32] }
+
+    /// Returns bytes of the transaction id in the internal (little-endian)
+    /// byte order, as produced by the hash function and serialized in
+    /// consensus data (like transaction input outpoints).
+    ///
+    /// This is the same as [`ByteArray::to_byte_array`].
+    #[inline]
+    pub fn to_consensus_bytes(&self) -> [u8; 32] { self.to_byte_array() }
+
+    /// Returns bytes of the transaction id in the reversed (big-endian) byte
+    /// order, as used by the `Display` implementation, block explorers and
+    /// Bitcoin Core RPC.
+    #[inline]
+    pub fn to_display_bytes(&self) -> [u8; 32] {
+        let mut bytes = self.to_byte_array();
+        bytes.reverse();
+        bytes
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, From, Error)]
+#[display(doc_comments)]
+pub enum TxidParseError {
+    /// invalid transaction id length: expected 64 hex chars, got {0}.
+    InvalidLength(usize),
+
+    /// malformed transaction id hex value. Details: {0}
+    #[from]
+    InvalidHex(hex::Error),
+}
+
+impl FromStr for Txid {
+    type Err = TxidParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 {
+            return Err(TxidParseError::InvalidLength(s.len()));
+        }
+        Bytes32StrRev::from_str(s).map(Self).map_err(TxidParseError::from)
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display, From)]
@@ -67,8 +120,19 @@ impl Txid {
 // 0xFFFFFFFF used in coinbase
 pub struct Vout(u32);
 
+/// Error converting transaction input or output index.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display("transaction input or output index {0} exceeds the maximal value of 2^32-1")]
+pub struct TxIndexError(pub usize);
+
 impl Vout {
     pub const fn from_u32(u: u32) -> Self { Vout(u) }
+    /// Converts `usize` output index into [`Vout`], erroring if the index
+    /// exceeds `u32::MAX` (which is possible on 64-bit platforms).
+    #[inline]
+    pub fn try_from_usize(index: usize) -> Result<Self, TxIndexError> {
+        u32::try_from(index).map(Self).map_err(|_| TxIndexError(index))
+    }
     #[inline]
     pub const fn into_u32(self) -> u32 { self.0 }
     #[inline]
@@ -79,6 +143,13 @@ impl Vout {
     pub const fn to_usize(&self) -> usize { self.0 as usize }
 }
 
+impl TryFrom<usize> for Vout {
+    type Error = TxIndexError;
+
+    #[inline]
+    fn try_from(index: usize) -> Result<Self, Self::Error> { Self::try_from_usize(index) }
+}
+
 impl FromStr for Vout {
     type Err = ParseIntError;
 
@@ -86,6 +157,11 @@ impl FromStr for Vout {
     fn from_str(s: &str) -> Result<Self, Self::Err> { s.parse().map(Self) }
 }
 
+/// Reference to a transaction output.
+///
+/// Outpoints are ordered first by their [`Txid`] (using its consensus byte
+/// order) and then by the numeric value of the output number. This ordering is
+/// guaranteed to remain stable.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
@@ -135,7 +211,7 @@ pub enum OutpointParseError {
 
     /// malformed outpoint txid value. Details: {0}
     #[from]
-    InvalidTxid(hex::Error),
+    InvalidTxid(TxidParseError),
 }
 
 impl FromStr for Outpoint {
@@ -225,7 +301,6 @@ pub struct TxIn {
 #[wrapper_mut(MathAssign)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Sats(
     #[from]
     #[from(u32)]
@@ -347,6 +422,56 @@ impl Display for Sats {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(&self.0, f) }
 }
 
+/// Human-readable serialization of [`Sats`] uses decimal strings, since the
+/// values may exceed 2^53 and lose precision when parsed as JSON numbers in
+/// JavaScript. Deserialization also accepts plain numbers for compatibility.
+/// Binary serialization uses `u64`.
+#[cfg(feature = "serde")]
+mod _serde_sats {
+    use serde::de::{Error, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    impl Serialize for Sats {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+            if serializer.is_human_readable() {
+                serializer.serialize_str(&self.to_string())
+            } else {
+                serializer.serialize_u64(self.0)
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Sats {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+            if deserializer.is_human_readable() {
+                struct SatsVisitor;
+
+                impl Visitor<'_> for SatsVisitor {
+                    type Value = Sats;
+
+                    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                        write!(formatter, "a number of satoshis as a decimal string or integer")
+                    }
+
+                    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> { Ok(Sats(v)) }
+
+                    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                        v.parse().map(Sats).map_err(|_| E::custom("invalid number of satoshis"))
+                    }
+                }
+
+                deserializer.deserialize_any(SatsVisitor)
+            } else {
+                u64::deserialize(deserializer).map(Sats)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
@@ -363,9 +488,23 @@ impl TxOut {
             value: value.into(),
         }
     }
+
+    /// Constructs zero-value `OP_RETURN` output with the `data` pushed by the
+    /// script pubkey.
+    ///
+    /// # Errors
+    ///
+    /// If the data length exceeds the standard [`MAX_OP_RETURN_DATA`] limit.
+    pub fn op_return(data: &[u8]) -> Result<Self, ScriptError> {
+        if data.len() > MAX_OP_RETURN_DATA {
+            return Err(ScriptError::OpReturnDataSize(data.len()));
+        }
+        Ok(TxOut::new(ScriptPubkey::op_return(data), Sats::ZERO))
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(inner)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -396,6 +535,14 @@ impl TxVer {
     #[inline]
     pub const fn is_standard(self) -> bool { self.0 <= TxVer::V2.0 }
 
+    /// Detects whether the version is 2 or above, enabling BIP68 relative
+    /// time locks in input sequence numbers.
+    ///
+    /// Like in Bitcoin Core, the version is compared as an unsigned number,
+    /// so negative versions also enable BIP68.
+    #[inline]
+    pub const fn is_v2(self) -> bool { self.0 as u32 >= TxVer::V2.0 as u32 }
+
     #[inline]
     pub const fn to_consensus_i32(&self) -> i32 { self.0 }
 }
@@ -414,7 +561,7 @@ pub struct Tx {
 
 impl LowerHex for Tx {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.consensus_serialize().to_hex())
+        f.write_str(&self.consensus_serialize_hex())
     }
 }
 
@@ -430,22 +577,134 @@ pub enum BlockDataParseError {
 impl FromStr for Tx {
     type Err = BlockDataParseError;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let data = Vec::<u8>::from_hex(s)?;
-        Tx::consensus_deserialize(data).map_err(BlockDataParseError::from)
-    }
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Tx::consensus_deserialize_hex(s) }
 }
 
 impl Tx {
+    /// Constructs transaction without inputs and outputs.
+    #[inline]
+    pub fn new(version: TxVer, lock_time: LockTime) -> Self {
+        Tx {
+            version,
+            inputs: none!(),
+            outputs: none!(),
+            lock_time,
+        }
+    }
+
+    /// Constructs a minimal version 2 transaction spending a single
+    /// `outpoint` into a single `output`.
+    ///
+    /// The input has an empty signature script and witness and the final
+    /// sequence number; the lock time is zero. Useful in tests and tooling
+    /// fabricating transactions which don't need to be valid.
+    pub fn spending(outpoint: Outpoint, output: TxOut) -> Self {
+        Tx {
+            version: TxVer::V2,
+            inputs: VarIntArray::from_checked(vec![TxIn {
+                prev_output: outpoint,
+                sig_script: none!(),
+                sequence: SeqNo::FINAL,
+                witness: none!(),
+            }]),
+            outputs: VarIntArray::from_checked(vec![output]),
+            lock_time: LockTime::ZERO,
+        }
+    }
+
+    /// Adds input to the transaction.
+    ///
+    /// # Errors
+    ///
+    /// If the number of transaction inputs exceeds `u32::MAX`.
+    #[inline]
+    pub fn push_input(&mut self, input: TxIn) -> Result<(), confinement::Error> {
+        self.inputs.push(input)
+    }
+
+    /// Adds output to the transaction.
+    ///
+    /// # Errors
+    ///
+    /// If the number of transaction outputs exceeds `u32::MAX`.
+    #[inline]
+    pub fn push_output(&mut self, output: TxOut) -> Result<(), confinement::Error> {
+        self.outputs.push(output)
+    }
+
     #[inline]
     pub fn inputs(&self) -> slice::Iter<TxIn> { self.inputs.iter() }
 
     #[inline]
     pub fn outputs(&self) -> slice::Iter<TxOut> { self.outputs.iter() }
 
+    /// Iterates over transaction inputs together with their index in the
+    /// transaction.
+    #[inline]
+    pub fn inputs_with_index(&self) -> impl Iterator<Item = (u32, &TxIn)> {
+        // The number of inputs is confined to `u32`, so the cast is lossless
+        self.inputs().enumerate().map(|(no, txin)| (no as u32, txin))
+    }
+
+    /// Iterates over transaction outputs together with their [`Vout`].
+    #[inline]
+    pub fn outputs_with_index(&self) -> impl Iterator<Item = (Vout, &TxOut)> {
+        // The number of outputs is confined to `u32`, so the cast is lossless
+        self.outputs().enumerate().map(|(no, txout)| (Vout::from_u32(no as u32), txout))
+    }
+
+    /// Finds the first transaction output matching the predicate, returning it
+    /// together with its [`Vout`].
+    #[inline]
+    pub fn find_output(&self, pred: impl FnMut(&TxOut) -> bool) -> Option<(Vout, &TxOut)> {
+        self.find_outputs(pred).next()
+    }
+
+    /// Iterates over all transaction outputs matching the predicate together
+    /// with their [`Vout`].
+    #[inline]
+    pub fn find_outputs(
+        &self,
+        mut pred: impl FnMut(&TxOut) -> bool,
+    ) -> impl Iterator<Item = (Vout, &TxOut)> {
+        self.outputs_with_index().filter(move |(_, txout)| pred(txout))
+    }
+
+    /// Iterates over the outpoints created by the transaction together with
+    /// the outputs they refer to. The transaction id is computed only once.
+    pub fn created_outpoints(&self) -> impl Iterator<Item = (Outpoint, &TxOut)> {
+        let txid = self.txid();
+        self.outputs_with_index().map(move |(vout, txout)| (Outpoint::new(txid, vout), txout))
+    }
+
     #[inline]
     pub fn is_segwit(&self) -> bool { self.inputs().any(|txin| !txin.witness.is_empty()) }
 
+    /// Detects whether the transaction signals opt-in replace-by-fee
+    /// according to BIP-125, i.e. whether any of its inputs has a sequence
+    /// number below `0xFFFFFFFE`.
+    #[inline]
+    pub fn is_rbf_signaling(&self) -> bool { self.inputs().any(|txin| txin.sequence.is_rbf()) }
+
+    /// Checks whether the transaction is final for inclusion into a block at a
+    /// given `height`, which median time past is `mtp`, according to the
+    /// consensus rules for the transaction lock time.
+    ///
+    /// Transaction is final if its lock time is zero, or if it is below the
+    /// block height (for height-based lock times) or median time past (for
+    /// time-based lock times), or if all transaction inputs are final.
+    pub fn is_final(&self, height: u32, mtp: u32) -> bool {
+        let lock_time = self.lock_time.to_consensus_u32();
+        if lock_time == 0 {
+            return true;
+        }
+        let threshold = if self.lock_time.is_height_based() { height } else { mtp };
+        if lock_time < threshold {
+            return true;
+        }
+        self.inputs().all(|txin| txin.sequence.is_final())
+    }
+
     #[inline]
     pub fn to_unsigned_tx(&self) -> Tx {
         let mut tx = self.clone();
@@ -456,12 +715,64 @@ impl Tx {
         tx
     }
 
+    /// Sorts transaction inputs and outputs according to BIP-69.
+    ///
+    /// Inputs are sorted by the previous transaction id and then by the output
+    /// number. Following BIP-69, the ids are compared in the reversed byte
+    /// order (i.e. the one used by their string representation, see
+    /// [`Txid::to_display_bytes`]), which is different from the order given
+    /// by [`Txid`] `Ord` implementation. Outputs are sorted by their value and
+    /// then lexicographically by their `scriptPubkey` bytes.
+    ///
+    /// NB: Sorting invalidates any existing signatures.
+    pub fn sort_bip69(&mut self) {
+        let mut inputs = mem::take(&mut self.inputs).release();
+        inputs.sort_by(|a, b| {
+            let a = &a.prev_output;
+            let b = &b.prev_output;
+            (a.txid.to_display_bytes(), a.vout).cmp(&(b.txid.to_display_bytes(), b.vout))
+        });
+        self.inputs = VarIntArray::from_checked(inputs);
+
+        let mut outputs = mem::take(&mut self.outputs).release();
+        outputs.sort_by(|a, b| {
+            (a.value, a.script_pubkey.as_slice()).cmp(&(b.value, b.script_pubkey.as_slice()))
+        });
+        self.outputs = VarIntArray::from_checked(outputs);
+    }
+
     /// Computes a "normalized TXID" which does not include any signatures.
     ///
     /// This gives a way to identify a transaction that is "the same" as
     /// another in the sense of having same inputs and outputs.
     pub fn ntxid(&self) -> [u8; 32] { self.to_unsigned_tx().txid().to_byte_array() }
 
+    /// Splits transaction consensus serialization into its non-segwit and
+    /// segwit parts.
+    ///
+    /// The first returned value is the serialization without any segwit data,
+    /// which is used as the [`Txid`] preimage. The second one contains segwit
+    /// data only: the marker and flag bytes followed by the witness of each
+    /// input; it is empty for non-segwit transactions. Thus, the weight of the
+    /// transaction equals to four times the length of the first part plus the
+    /// length of the second part.
+    pub fn serialize_parts(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut base = vec![];
+        self.version.consensus_encode(&mut base).expect("in-memory writing can't fail");
+        self.inputs.consensus_encode(&mut base).expect("in-memory writing can't fail");
+        self.outputs.consensus_encode(&mut base).expect("in-memory writing can't fail");
+        self.lock_time.consensus_encode(&mut base).expect("in-memory writing can't fail");
+
+        let mut witness = vec![];
+        if self.is_segwit() {
+            witness.extend([0x00, 0x01]);
+            for input in self.inputs() {
+                input.witness.consensus_encode(&mut witness).expect("in-memory writing can't fail");
+            }
+        }
+        (base, witness)
+    }
+
     /// Computes the [`Txid`].
     ///
     /// Hashes the transaction **excluding** the segwit data (i.e. the marker,
@@ -469,14 +780,12 @@ impl Tx {
     /// transactions which do not have any segwit data, this will be equal
     /// to [`Tx::wtxid()`].
     pub fn txid(&self) -> Txid {
-        let mut enc = Sha256::default();
+        let mut enc = Sha256d::new();
         self.version.consensus_encode(&mut enc).expect("engines don't error");
         self.inputs.consensus_encode(&mut enc).expect("engines don't error");
         self.outputs.consensus_encode(&mut enc).expect("engines don't error");
         self.lock_time.consensus_encode(&mut enc).expect("engines don't error");
-        let mut double = Sha256::default();
-        double.input_raw(&enc.finish());
-        Txid::from_byte_array(double.finish())
+        Txid::from_byte_array(enc.finish())
     }
 
     /// Computes the segwit version of the transaction id.
@@ -486,16 +795,16 @@ impl Tx {
     /// transactions which do not have any segwit data, this will be equal
     /// to [`Transaction::txid()`].
     pub fn wtxid(&self) -> Wtxid {
-        let mut enc = Sha256::default();
+        let mut enc = Sha256d::new();
         self.consensus_encode(&mut enc).expect("engines don't error");
-        let mut double = Sha256::default();
-        double.input_raw(&enc.finish());
-        Wtxid::from_byte_array(double.finish())
+        Wtxid::from_byte_array(enc.finish())
     }
 }
 
 #[cfg(test)]
 mod test {
+    use amplify::hex::{FromHex, ToHex};
+
     use super::*;
 
     #[test]
@@ -510,6 +819,308 @@ mod test {
         assert_eq!(from_str[0], 0xca);
     }
 
+    #[test]
+    fn txout_op_return() {
+        let txout = TxOut::op_return(&[0xAB; 80]).unwrap();
+        assert_eq!(txout.value, Sats::ZERO);
+        assert!(txout.script_pubkey.is_op_return());
+        assert_eq!(txout.script_pubkey.len(), 83);
+        assert_eq!(TxOut::op_return(&[0xAB; 81]), Err(ScriptError::OpReturnDataSize(81)));
+    }
+
+    #[test]
+    fn vout_from_usize() {
+        assert_eq!(Vout::try_from_usize(7), Ok(Vout::from_u32(7)));
+        assert_eq!(Vout::try_from(u32::MAX as usize), Ok(Vout::from_u32(u32::MAX)));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(Vout::try_from_usize(u32::MAX as usize + 1), Err(TxIndexError(1 << 32)));
+    }
+
+    #[test]
+    fn txid_consensus_display_bytes() {
+        let hex = "ed9f6388c0360c1861d331a0388d5a54815dd720cc67fa783c348217a0e943ca";
+        let txid = Txid::from_str(hex).unwrap();
+        let display = txid.to_display_bytes();
+        let mut consensus = txid.to_consensus_bytes();
+        assert_eq!(display.to_vec(), Vec::<u8>::from_hex(hex).unwrap());
+        assert_eq!(consensus, txid.to_byte_array());
+        assert_eq!(consensus[0], 0xca);
+        assert_eq!(display[0], 0xed);
+        consensus.reverse();
+        assert_eq!(consensus, display);
+        assert_eq!(Txid::from(txid.to_consensus_bytes()), txid);
+    }
+
+    #[test]
+    fn outpoint_ord() {
+        use std::collections::BTreeMap;
+
+        // The first txid is greater in the display order but smaller in the consensus order
+        let txid1 =
+            Txid::from_str("ff00000000000000000000000000000000000000000000000000000000000001")
+                .unwrap();
+        let txid2 =
+            Txid::from_str("0100000000000000000000000000000000000000000000000000000000000002")
+                .unwrap();
+        assert!(txid1 < txid2);
+        assert!(txid1.to_consensus_bytes() < txid2.to_consensus_bytes());
+        assert!(txid1.to_display_bytes() > txid2.to_display_bytes());
+
+        let outpoints = [
+            Outpoint::new(txid2, 0u32),
+            Outpoint::new(txid1, 256u32),
+            Outpoint::new(txid2, 1u32),
+            Outpoint::new(txid1, 1u32),
+        ];
+        let map = outpoints.into_iter().map(|outpoint| (outpoint, ())).collect::<BTreeMap<_, _>>();
+
+        let mut expected = outpoints.to_vec();
+        expected
+            .sort_by_key(|outpoint| (outpoint.txid.to_consensus_bytes(), outpoint.vout.to_u32()));
+        assert_eq!(map.into_keys().collect::<Vec<_>>(), expected);
+        assert_eq!(expected[0], Outpoint::new(txid1, 1u32));
+        assert_eq!(expected[1], Outpoint::new(txid1, 256u32));
+    }
+
+    /// Transaction spending a single input with the given signature script
+    /// and witness stack into P2WPKH outputs with the given values.
+    fn test_tx<const N: usize>(sig_script: Vec<u8>, witness: [Vec<u8>; N], values: &[u64]) -> Tx {
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_input(TxIn {
+            prev_output: Outpoint::new(Txid::coinbase(), 0u32),
+            sig_script: SigScript::from_unsafe(sig_script),
+            sequence: SeqNo::FINAL,
+            witness: Witness::from_consensus_stack(witness),
+        })
+        .unwrap();
+        for value in values {
+            tx.push_output(TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), *value)).unwrap();
+        }
+        tx
+    }
+
+    #[test]
+    fn ntxid_malleation() {
+        let original = test_tx(vec![0x01, 0xAA], [vec![0xAA; 72]], &[1000]);
+        // Malleated variant with a different push encoding and signature
+        let malleated = test_tx(vec![0x4C, 0x01, 0xAA], [vec![0xBB; 71]], &[1000]);
+
+        assert_ne!(original.txid(), malleated.txid());
+        assert_ne!(original.wtxid(), malleated.wtxid());
+        assert_eq!(original.ntxid(), malleated.ntxid());
+        assert_eq!(original.ntxid(), original.to_unsigned_tx().txid().to_byte_array());
+
+        let modified = test_tx(vec![0x01, 0xAA], [vec![0xAA; 72]], &[999]);
+        assert_ne!(original.ntxid(), modified.ntxid());
+    }
+
+    #[test]
+    fn spending() {
+        let outpoint = Outpoint::new(Txid::from([1u8; 32]), 2u32);
+        let output = TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64);
+        let tx = Tx::spending(outpoint, output.clone());
+        assert_eq!(tx.version, TxVer::V2);
+        assert_eq!(tx.lock_time, LockTime::ZERO);
+        assert_eq!(tx.inputs.len(), 1);
+        assert_eq!(tx.inputs[0].prev_output, outpoint);
+        assert_eq!(tx.inputs[0].sequence, SeqNo::FINAL);
+        assert!(tx.inputs[0].sig_script.is_empty() && tx.inputs[0].witness.is_empty());
+        assert_eq!(tx.outputs.as_slice(), &[output]);
+        assert_eq!(Tx::consensus_deserialize(tx.consensus_serialize()), Ok(tx));
+    }
+
+    #[test]
+    fn created_outpoints() {
+        let tx = test_tx(vec![0x51], [], &[1000, 2000, 3000]);
+        let txid = tx.txid();
+        let created = tx.created_outpoints().collect::<Vec<_>>();
+        assert_eq!(created.len(), 3);
+        for (no, (outpoint, txout)) in created.into_iter().enumerate() {
+            assert_eq!(outpoint, Outpoint::new(txid, no as u32));
+            assert_eq!(txout, &tx.outputs[no]);
+        }
+    }
+
+    #[test]
+    fn find_outputs() {
+        let mut tx = Tx::spending(
+            Outpoint::new(Txid::coinbase(), 0u32),
+            TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64),
+        );
+        tx.push_output(TxOut::new(ScriptPubkey::op_return(&[1u8; 32]), 0u64)).unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::p2wpkh([2u8; 20]), 2000u64)).unwrap();
+
+        let (vout, txout) = tx.find_output(|txout| txout.script_pubkey.is_op_return()).unwrap();
+        assert_eq!(vout, Vout::from_u32(1));
+        assert_eq!(txout, &tx.outputs[1]);
+        assert_eq!(tx.find_output(|txout| txout.script_pubkey.is_p2tr()), None);
+
+        let found = tx.find_outputs(|txout| txout.script_pubkey.is_p2wpkh()).collect::<Vec<_>>();
+        assert_eq!(found, vec![
+            (Vout::from_u32(0), &tx.outputs[0]),
+            (Vout::from_u32(2), &tx.outputs[2])
+        ]);
+        assert_eq!(tx.find_outputs(|txout| txout.script_pubkey.is_p2tr()).count(), 0);
+    }
+
+    #[test]
+    fn serialize_parts() {
+        let tx = test_tx(vec![0x01, 0xAA], [vec![0xAA; 72], vec![0x02; 33]], &[1000]);
+
+        let (base, witness) = tx.serialize_parts();
+        let unsigned = tx.to_unsigned_tx();
+        let mut stripped = tx.clone();
+        for input in &mut stripped.inputs {
+            input.witness = empty!();
+        }
+        assert_eq!(base, stripped.consensus_serialize());
+        assert_eq!(&witness[..2], &[0x00, 0x01]);
+        assert_eq!(base.len() + witness.len(), tx.consensus_serialize().len());
+        assert_eq!(
+            crate::Weight::weight_units(&tx).to_u32() as usize,
+            base.len() * 4 + witness.len()
+        );
+
+        let (base, witness) = unsigned.serialize_parts();
+        assert!(witness.is_empty());
+        assert_eq!(base, unsigned.consensus_serialize());
+    }
+
+    #[test]
+    fn sort_bip69() {
+        // Greater in the consensus byte order but smaller in the display one
+        let txid1 =
+            Txid::from_str("0100000000000000000000000000000000000000000000000000000000000002")
+                .unwrap();
+        let txid2 =
+            Txid::from_str("ff00000000000000000000000000000000000000000000000000000000000001")
+                .unwrap();
+        assert!(txid1 > txid2);
+
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        for prev_output in
+            [Outpoint::new(txid2, 0u32), Outpoint::new(txid1, 256u32), Outpoint::new(txid1, 2u32)]
+        {
+            tx.push_input(TxIn {
+                prev_output,
+                sig_script: none!(),
+                sequence: SeqNo::FINAL,
+                witness: none!(),
+            })
+            .unwrap();
+        }
+        for (script, value) in [
+            (ScriptPubkey::p2wpkh([2u8; 20]), 2000u64),
+            (ScriptPubkey::p2wpkh([2u8; 20]), 1000u64),
+            (ScriptPubkey::p2wpkh([1u8; 20]), 2000u64),
+        ] {
+            tx.push_output(TxOut::new(script, value)).unwrap();
+        }
+
+        tx.sort_bip69();
+        assert_eq!(tx.inputs().map(|txin| txin.prev_output).collect::<Vec<_>>(), vec![
+            Outpoint::new(txid1, 2u32),
+            Outpoint::new(txid1, 256u32),
+            Outpoint::new(txid2, 0u32),
+        ]);
+        assert_eq!(tx.outputs().cloned().collect::<Vec<_>>(), vec![
+            TxOut::new(ScriptPubkey::p2wpkh([2u8; 20]), 1000u64),
+            TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 2000u64),
+            TxOut::new(ScriptPubkey::p2wpkh([2u8; 20]), 2000u64),
+        ]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn sats_serde() {
+        let sats = Sats::from_sats((1u64 << 53) + 1);
+        let json = serde_json::to_string(&sats).unwrap();
+        assert_eq!(json, "\"9007199254740993\"");
+        assert_eq!(serde_json::from_str::<Sats>(&json).unwrap(), sats);
+        assert_eq!(serde_json::from_str::<Sats>("9007199254740993").unwrap(), sats);
+        assert!(serde_json::from_str::<Sats>("\"-1\"").is_err());
+        assert!(serde_json::from_str::<Sats>("\"1.5\"").is_err());
+
+        let txout = TxOut::new(ScriptPubkey::new(), Sats::from_sats(u64::MAX));
+        let json = serde_json::to_value(&txout).unwrap();
+        assert_eq!(json["value"], "18446744073709551615");
+        assert_eq!(serde_json::from_value::<TxOut>(json).unwrap(), txout);
+    }
+
+    #[test]
+    fn txid_wrong_len() {
+        let short = "ed9f6388c0360c1861d331a0388d5a54815dd720cc67fa783c348217a0e943c";
+        let err = Txid::from_str(short).unwrap_err();
+        assert_eq!(err, TxidParseError::InvalidLength(63));
+        assert_eq!(
+            err.to_string(),
+            "invalid transaction id length: expected 64 hex chars, got 63."
+        );
+
+        let outpoint = format!("{short}ab:1");
+        assert_eq!(
+            Outpoint::from_str(&outpoint).unwrap_err(),
+            OutpointParseError::InvalidTxid(TxidParseError::InvalidLength(65))
+        );
+    }
+
+    #[test]
+    fn tx_ver() {
+        assert!(!TxVer::V1.is_v2());
+        assert!(TxVer::V2.is_v2());
+        assert!(TxVer::from_consensus_i32(3).is_v2());
+        assert!(TxVer::from_consensus_i32(-1).is_v2());
+        assert!(!TxVer::from_consensus_i32(0).is_v2());
+        assert_eq!(TxVer::V2.to_string(), "2");
+        assert_eq!(TxVer::from_consensus_i32(-1).to_string(), "-1");
+    }
+
+    #[test]
+    fn tx_builder() {
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        assert_eq!(tx.inputs.len(), 0);
+        assert_eq!(tx.outputs.len(), 0);
+        tx.push_input(TxIn {
+            prev_output: Outpoint::coinbase(),
+            sig_script: none!(),
+            sequence: SeqNo::ZERO,
+            witness: none!(),
+        })
+        .unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::op_return(&[]), Sats::ZERO)).unwrap();
+        assert_eq!(tx.inputs.len(), 1);
+        assert_eq!(tx.outputs_with_index().next().unwrap().0, Vout::from_u32(0));
+    }
+
+    #[test]
+    fn rbf_and_finality() {
+        let mut tx = Tx::new(TxVer::V2, LockTime::from_height(800_000).unwrap());
+        tx.push_input(TxIn {
+            prev_output: Outpoint::coinbase(),
+            sig_script: none!(),
+            sequence: SeqNo::MAX_NON_RBF,
+            witness: none!(),
+        })
+        .unwrap();
+        assert!(!tx.is_rbf_signaling());
+        assert!(!tx.is_final(800_000, 0));
+        assert!(tx.is_final(800_001, 0));
+
+        for txin in &mut tx.inputs {
+            txin.sequence = SeqNo::ZERO;
+        }
+        assert!(tx.is_rbf_signaling());
+
+        for txin in &mut tx.inputs {
+            txin.sequence = SeqNo::FINAL;
+        }
+        assert!(!tx.is_rbf_signaling());
+        assert!(tx.is_final(800_000, 0));
+
+        let tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        assert!(tx.is_final(0, 0));
+    }
+
     #[test]
     fn sats() {
         assert_eq!(Sats(0).0, 0);
@@ -564,6 +1175,8 @@ mod test {
 
         assert_eq!(&realtx.to_string(), tx);
         assert_eq!(&realtx.to_hex(), tx);
+        assert_eq!(&realtx.consensus_serialize_hex(), tx);
+        assert_eq!(Tx::consensus_deserialize_hex(tx).unwrap(), realtx);
         assert_eq!(&format!("{realtx:x}"), tx);
 
         // All these tests aren't really needed because if they fail, the hash check at
@@ -609,6 +1222,8 @@ mod test {
 
         assert_eq!(&realtx.to_string(), tx);
         assert_eq!(&realtx.to_hex(), tx);
+        assert_eq!(&realtx.consensus_serialize_hex(), tx);
+        assert_eq!(Tx::consensus_deserialize_hex(tx).unwrap(), realtx);
         assert_eq!(&format!("{realtx:x}"), tx);
 
         // All these tests aren't really needed because if they fail, the hash check at
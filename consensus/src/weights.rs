@@ -20,14 +20,24 @@
 // limitations under the License.
 
 use std::iter::Sum;
+use std::num::ParseIntError;
 use std::ops::{Add, AddAssign};
+use std::str::FromStr;
 
-use crate::{LenVarInt, ScriptPubkey, SigScript, Tx, TxIn, TxOut, Witness, LIB_NAME_BITCOIN};
+use crate::{LenVarInt, Sats, ScriptPubkey, SigScript, Tx, TxIn, TxOut, Witness, LIB_NAME_BITCOIN};
+
+/// Fee rate, in satoshis per virtual byte, used by Bitcoin Core by default for
+/// the detection of dust outputs (`-dustrelayfee` option).
+pub const DUST_RELAY_FEE_RATE: u64 = 3;
+
+/// Size of a transaction input with an empty `scriptSig`: previous output
+/// (36 bytes), `scriptSig` length (1 byte) and sequence number (4 bytes).
+pub const TXIN_BASE_SIZE: usize = 36 + 1 + 4;
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
 #[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
-#[display("{0} vbytes")]
+#[display("{0} vB")]
 pub struct VBytes(u32);
 
 impl Add for VBytes {
@@ -43,6 +53,18 @@ impl Sum for VBytes {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { Self(iter.map(Self::into_u32).sum()) }
 }
 
+impl FromStr for VBytes {
+    type Err = ParseIntError;
+
+    /// Parses virtual bytes value, accepting an optional `vB` (or legacy
+    /// `vbytes`) unit suffix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_suffix("vbytes").or_else(|| s.strip_suffix("vB")).unwrap_or(s);
+        u32::from_str(s.trim_end()).map(Self)
+    }
+}
+
 impl VBytes {
     pub fn to_u32(&self) -> u32 { self.0 }
     pub fn into_u32(self) -> u32 { self.0 }
@@ -68,7 +90,19 @@ impl Sum for WeightUnits {
 }
 
 impl From<WeightUnits> for VBytes {
-    fn from(wu: WeightUnits) -> Self { Self((wu.0 as f32 / 4.0).ceil() as u32) }
+    /// Converts weight units into virtual bytes, rounding up.
+    fn from(wu: WeightUnits) -> Self { Self(wu.0.div_ceil(4)) }
+}
+
+impl FromStr for WeightUnits {
+    type Err = ParseIntError;
+
+    /// Parses weight units value, accepting an optional `WU` unit suffix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_suffix("WU").unwrap_or(s);
+        u32::from_str(s.trim_end()).map(Self)
+    }
 }
 
 impl WeightUnits {
@@ -78,6 +112,62 @@ impl WeightUnits {
     pub fn into_u32(self) -> u32 { self.0 }
 }
 
+impl ScriptPubkey {
+    /// Returns typical virtual size of a transaction input spending an output
+    /// with this script pubkey, or `None` if the output type is not recognized
+    /// or its spending size depends on an unknown script (like in P2SH and
+    /// P2WSH cases).
+    ///
+    /// For P2PKH and P2WPKH outputs the size assumes a 72-byte ECDSA signature
+    /// and a compressed public key; P2TR outputs are assumed to be spent via
+    /// the key path with a default sighash type.
+    pub fn spend_vsize(&self) -> Option<VBytes> {
+        let base = TXIN_BASE_SIZE as u32;
+        if self.is_p2pkh() {
+            // Pushes of signature with the sighash type and a public key.
+            Some(VBytes(base + 1 + 72 + 1 + 33))
+        } else if self.is_p2wpkh() {
+            // Witness stack length, signature and public key items.
+            Some(VBytes(base + (1 + 1 + 72 + 1 + 33u32).div_ceil(4)))
+        } else if self.is_p2tr() {
+            // Witness stack length and a single signature item.
+            Some(VBytes(base + (1 + 1 + 64u32).div_ceil(4)))
+        } else {
+            None
+        }
+    }
+}
+
+impl TxOut {
+    /// Computes minimal value for the output not to be a dust under the
+    /// [`DUST_RELAY_FEE_RATE`], matching `GetDustThreshold` of the Bitcoin
+    /// Core relay policy.
+    ///
+    /// The value is the fee for the output size plus the size of an input
+    /// spending it, which is estimated as 148 bytes for non-witness outputs
+    /// and 67 virtual bytes for witness outputs (of any witness version),
+    /// regardless of the actual output type. Use [`ScriptPubkey::spend_vsize`]
+    /// for more precise estimates of the spending costs. `OP_RETURN` outputs
+    /// are never dust.
+    pub fn dust_threshold(&self) -> Sats {
+        if self.script_pubkey.is_op_return() {
+            return Sats::ZERO;
+        }
+        let spend_vsize = if self.script_pubkey.is_witness_program() {
+            32 + 4 + 1 + 107 / 4 + 4
+        } else {
+            32 + 4 + 1 + 107 + 4
+        };
+        let vsize = self.vbytes().into_u32() + spend_vsize;
+        Sats::from(vsize as u64 * DUST_RELAY_FEE_RATE)
+    }
+
+    /// Detects whether the output value is below the
+    /// [`TxOut::dust_threshold`].
+    #[inline]
+    pub fn is_dust(&self) -> bool { self.value < self.dust_threshold() }
+}
+
 pub trait Weight {
     fn weight_units(&self) -> WeightUnits;
 
@@ -140,3 +230,67 @@ impl Weight for Witness {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::InternalPk;
+
+    #[test]
+    fn display_from_str() {
+        let wu = WeightUnits(565);
+        assert_eq!(wu.to_string(), "565 WU");
+        assert_eq!(WeightUnits::from_str("565 WU").unwrap(), wu);
+        assert_eq!(WeightUnits::from_str("565WU").unwrap(), wu);
+        assert_eq!(WeightUnits::from_str("565").unwrap(), wu);
+        assert!(WeightUnits::from_str("565 vB").is_err());
+
+        let vb = VBytes::from(wu);
+        assert_eq!(vb, VBytes(142));
+        assert_eq!(vb.to_string(), "142 vB");
+        assert_eq!(VBytes::from_str("142 vB").unwrap(), vb);
+        assert_eq!(VBytes::from_str("142 vbytes").unwrap(), vb);
+        assert_eq!(VBytes::from_str("142").unwrap(), vb);
+        assert!(VBytes::from_str("142 WU").is_err());
+    }
+
+    #[test]
+    fn dust() {
+        let p2pkh = TxOut::new(ScriptPubkey::p2pkh([1u8; 20]), 546u64);
+        assert_eq!(p2pkh.script_pubkey.spend_vsize(), Some(VBytes(148)));
+        assert_eq!(p2pkh.dust_threshold(), Sats::from(546u64));
+        assert!(!p2pkh.is_dust());
+        assert!(TxOut::new(p2pkh.script_pubkey, 545u64).is_dust());
+
+        let p2wpkh = TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 294u64);
+        assert_eq!(p2wpkh.script_pubkey.spend_vsize(), Some(VBytes(68)));
+        assert_eq!(p2wpkh.dust_threshold(), Sats::from(294u64));
+        assert!(!p2wpkh.is_dust());
+        assert!(TxOut::new(p2wpkh.script_pubkey, 293u64).is_dust());
+
+        // Relay policy uses the same spending estimate for all witness
+        // versions, even though a key path spend is smaller
+        let internal_pk = InternalPk::from_byte_array(InternalPk::NUMS).unwrap();
+        let p2tr = TxOut::new(ScriptPubkey::p2tr_key_only(internal_pk), 330u64);
+        assert_eq!(p2tr.script_pubkey.spend_vsize(), Some(VBytes(58)));
+        assert_eq!(p2tr.dust_threshold(), Sats::from(330u64));
+        assert!(!p2tr.is_dust());
+        assert!(TxOut::new(p2tr.script_pubkey, 329u64).is_dust());
+
+        let p2wsh = TxOut::new(ScriptPubkey::p2wsh([1u8; 32]), 0u64);
+        assert_eq!(p2wsh.script_pubkey.spend_vsize(), None);
+        assert_eq!(p2wsh.dust_threshold(), Sats::from(330u64));
+        let p2sh = TxOut::new(ScriptPubkey::p2sh([1u8; 20]), 0u64);
+        assert_eq!(p2sh.dust_threshold(), Sats::from(540u64));
+
+        assert!(!TxOut::op_return(b"data").unwrap().is_dust());
+    }
+
+    #[test]
+    fn vbytes_ceil() {
+        assert_eq!(VBytes::from(WeightUnits(0)), VBytes(0));
+        assert_eq!(VBytes::from(WeightUnits(564)), VBytes(141));
+        assert_eq!(VBytes::from(WeightUnits(565)), VBytes(142));
+        assert_eq!(VBytes::from(WeightUnits(u32::MAX)), VBytes(u32::MAX / 4 + 1));
+    }
+}
@@ -0,0 +1,67 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bc::ScriptPubkey;
+use commit_verify::mpc;
+use subtle::ConstantTimeEq;
+
+/// Constant-time equality for commitment values.
+///
+/// Commitments are not secret, but verification servers may prefer not to
+/// leak the length of a matching prefix through the comparison timing.
+pub trait CommitmentCtEq {
+    /// Compares two values in constant time, independently of the position of
+    /// the first differing byte.
+    fn ct_eq(&self, other: &Self) -> bool;
+}
+
+impl CommitmentCtEq for mpc::Commitment {
+    fn ct_eq(&self, other: &Self) -> bool { self.as_slice().ct_eq(other.as_slice()).into() }
+}
+
+impl CommitmentCtEq for ScriptPubkey {
+    fn ct_eq(&self, other: &Self) -> bool { self.as_slice().ct_eq(other.as_slice()).into() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn commitment_ct_eq() {
+        let a = mpc::Commitment::from([0xAB; 32]);
+        let mut b = [0xAB; 32];
+        assert!(a.ct_eq(&mpc::Commitment::from(b)));
+        b[31] = 0xAC;
+        assert!(!a.ct_eq(&mpc::Commitment::from(b)));
+        b[31] = 0xAB;
+        b[0] = 0x00;
+        assert!(!a.ct_eq(&mpc::Commitment::from(b)));
+    }
+
+    #[test]
+    fn script_pubkey_ct_eq() {
+        let a = ScriptPubkey::op_return(&[0xAB; 32]);
+        assert!(a.ct_eq(&ScriptPubkey::op_return(&[0xAB; 32])));
+        assert!(!a.ct_eq(&ScriptPubkey::op_return(&[0xAB; 31])));
+        assert!(!a.ct_eq(&ScriptPubkey::op_return(&[0xAC; 32])));
+    }
+}
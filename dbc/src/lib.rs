@@ -38,6 +38,13 @@
 //! transaction in a provable way, such that it can always be proven that a
 //! given transaction contains one and only one commitment of a specific type
 //! for a given commitment protocol.
+//!
+//! Commitment schemes are enabled with crate features: `tapret` for the
+//! taproot-based commitments and `opret` for the `OP_RETURN`-based ones (both
+//! are on by default). Clients using only `opret` may disable default features
+//! to avoid compiling the taproot commitment code. The optional `subtle`
+//! feature provides constant-time comparison of commitments with the
+//! `CommitmentCtEq` trait.
 
 #[macro_use]
 extern crate amplify;
@@ -51,10 +58,22 @@ extern crate commit_verify;
 /// Name of the strict type library generated from the data types in this crate.
 pub const LIB_NAME_BPCORE: &str = "BPCore";
 
+#[cfg(feature = "subtle")]
+mod ct;
 pub mod keytweak;
+mod merkle;
+#[cfg(feature = "opret")]
 pub mod opret;
 pub mod sigtweak;
+#[cfg(feature = "tapret")]
 pub mod tapret;
 mod proof;
+#[cfg(feature = "rayon")]
+mod scan;
 
-pub use proof::{Method, MethodParseError, Proof};
+#[cfg(feature = "subtle")]
+pub use ct::CommitmentCtEq;
+pub use merkle::merklize_leaves;
+pub use proof::{extract_commitment, Method, MethodParseError, Proof};
+#[cfg(feature = "rayon")]
+pub use scan::ParScanCommitments;
@@ -0,0 +1,83 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amplify::confinement::LargeVec;
+use amplify::{ByteArray, Bytes32};
+use commit_verify::{CommitEncode, MerkleHash};
+
+use crate::LIB_NAME_BPCORE;
+
+/// Merkle tree leaf committing to an arbitrary 32-byte value.
+#[derive(Wrapper, Copy, Clone, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = MerkleHash)]
+struct MerkleLeaf(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+/// Computes merkle root of a list of 32-byte leaves with the merklization
+/// algorithm of [`commit_verify`] ([`MerkleHash::merklize`]), without
+/// requiring the caller to implement `MerkleLeaves` for its own type.
+///
+/// Each leaf value is first hashed into a [`MerkleHash`] with the SHA256
+/// tagged hash using the [`MerkleHash`] commitment id tag
+/// (`<MerkleHash as CommitmentId>::TAG`), i.e. `SHA256(SHA256(tag) ||
+/// SHA256(tag) || leaf)`. The leaf hashes are then combined in the given
+/// order into a tree of `commit_verify` merkle nodes, which commit to the
+/// node depth and the total number of leaves, using the same tag.
+///
+/// # Panics
+///
+/// If the number of leaves exceeds 2^32-1.
+pub fn merklize_leaves(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let leaves = LargeVec::try_from_iter(leaves.iter().copied().map(MerkleLeaf::from))
+        .expect("number of merkle leaves exceeds 2^32-1");
+    MerkleHash::merklize(&leaves).to_byte_array()
+}
+
+#[cfg(test)]
+mod test {
+    use commit_verify::{CommitId, CommitmentId, DigestExt, Sha256};
+
+    use super::*;
+
+    #[test]
+    fn leaf_hash() {
+        let mut engine = Sha256::from_tag(<MerkleHash as CommitmentId>::TAG);
+        engine.input_raw(&[0xAA; 32]);
+        assert_eq!(MerkleLeaf::from([0xAA; 32]).commit_id(), MerkleHash::from(engine.finish()));
+    }
+
+    #[test]
+    fn merklize() {
+        let root = merklize_leaves(&[[1u8; 32], [2u8; 32]]);
+        assert_eq!(root, merklize_leaves(&[[1u8; 32], [2u8; 32]]));
+        assert_ne!(root, merklize_leaves(&[[2u8; 32], [1u8; 32]]));
+        assert_ne!(root, [1u8; 32]);
+        // The root commits to the number of leaves
+        assert_ne!(merklize_leaves(&[[1u8; 32]]), merklize_leaves(&[[1u8; 32], [1u8; 32]]));
+    }
+}
@@ -27,10 +27,10 @@ mod spk;
 
 use bc::Tx;
 use commit_verify::mpc::Commitment;
-use commit_verify::{CommitmentProtocol, EmbedCommitVerify, EmbedVerifyError};
+use commit_verify::{CommitmentProtocol, EmbedCommitProof, EmbedVerifyError};
 use strict_encoding::{StrictDeserialize, StrictSerialize};
 
-use crate::proof::Method;
+use crate::proof::{commitment_eq, opret_commitment, Method};
 use crate::{Proof, LIB_NAME_BPCORE};
 
 /// Marker non-instantiable enum defining LNPBP-12 taproot OP_RETURN (`tapret`)
@@ -50,6 +50,11 @@ pub enum OpretError {
     /// first OP_RETURN output inside the transaction already contains some
     /// data.
     InvalidOpretScript,
+
+    /// transaction contains an OP_RETURN output with a commitment, but it is
+    /// preceded by another OP_RETURN output, while the commitment must be
+    /// placed into the first one.
+    NotFirstOpret,
 }
 
 /// Empty type for use inside [`crate::Anchor`] for opret commitment scheme.
@@ -68,6 +73,42 @@ impl Proof for OpretProof {
     const METHOD: Method = Method::OpretFirst;
 
     fn verify(&self, msg: &Commitment, tx: &Tx) -> Result<(), EmbedVerifyError<OpretError>> {
-        tx.verify(msg, self)
+        // Checks the structure of the transaction OP_RETURN outputs
+        EmbedCommitProof::<_, Tx, OpretFirst>::restore_original_container(self, tx)?;
+        match opret_commitment(tx) {
+            Some((_, commitment)) if commitment_eq(&commitment, msg) => Ok(()),
+            _ => Err(EmbedVerifyError::CommitmentMismatch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U16;
+    use bc::{LockTime, ScriptPubkey, TxOut, TxVer};
+
+    use super::*;
+
+    #[test]
+    fn strict_roundtrip() {
+        let data = OpretProof::default().to_strict_serialized::<U16>().unwrap();
+        assert!(data.is_empty());
+        assert_eq!(OpretProof::from_strict_serialized::<U16>(data).unwrap(), OpretProof::default());
+    }
+
+    #[test]
+    fn proof_verify() {
+        let msg = Commitment::from([0xAA; 32]);
+        let proof = OpretProof::default();
+
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        assert_eq!(proof.verify(&msg, &tx), Err(OpretError::NoOpretOutput.into()));
+
+        tx.push_output(TxOut::new(ScriptPubkey::op_return(msg.as_slice()), 0u64)).unwrap();
+        assert_eq!(proof.verify(&msg, &tx), Ok(()));
+        assert_eq!(
+            proof.verify(&Commitment::from([0xAB; 32]), &tx),
+            Err(EmbedVerifyError::CommitmentMismatch)
+        );
     }
 }
@@ -31,13 +31,16 @@ impl EmbedCommitProof<Commitment, Tx, OpretFirst> for OpretProof {
         commit_container: &Tx,
     ) -> Result<Tx, EmbedVerifyError<OpretError>> {
         let mut tx = commit_container.clone();
-        for txout in &mut tx.outputs {
-            if txout.script_pubkey.is_op_return() {
-                *txout = self.restore_original_container(txout)?;
-                return Ok(tx);
-            }
+        let mut opret_outputs =
+            (&mut tx.outputs).into_iter().filter(|txout| txout.script_pubkey.is_op_return());
+        let first = opret_outputs.next().ok_or(OpretError::NoOpretOutput)?;
+        if first.script_pubkey.len() != 34
+            && opret_outputs.any(|txout| txout.script_pubkey.len() == 34)
+        {
+            return Err(OpretError::NotFirstOpret.into());
         }
-        Err(OpretError::NoOpretOutput.into())
+        *first = self.restore_original_container(&*first)?;
+        Ok(tx)
     }
 }
 
@@ -54,3 +57,37 @@ impl EmbedCommitVerify<Commitment, OpretFirst> for Tx {
         Err(OpretError::NoOpretOutput)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bc::opcodes::OP_RETURN;
+    use bc::{LockTime, ScriptPubkey, TxOut, TxVer};
+
+    use super::*;
+
+    fn verify(tx: &Tx, msg: &Commitment, proof: &OpretProof) -> bool {
+        <Tx as EmbedCommitVerify<Commitment, OpretFirst>>::verify(tx, msg, proof).is_ok()
+    }
+
+    #[test]
+    fn not_first_opret() {
+        let msg = Commitment::from([0xAA; 32]);
+        let empty_opret = TxOut::new(ScriptPubkey::from_unsafe(vec![OP_RETURN]), 0u64);
+
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_output(empty_opret.clone()).unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::op_return(msg.as_slice()), 0u64)).unwrap();
+        assert_eq!(
+            OpretProof::default().restore_original_container(&tx),
+            Err(EmbedVerifyError::from(OpretError::NotFirstOpret))
+        );
+        assert!(!verify(&tx, &msg, &OpretProof::default()));
+
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_output(empty_opret.clone()).unwrap();
+        let proof =
+            <Tx as EmbedCommitVerify<Commitment, OpretFirst>>::embed_commit(&mut tx, &msg).unwrap();
+        tx.push_output(empty_opret).unwrap();
+        assert!(verify(&tx, &msg, &proof));
+    }
+}
@@ -23,7 +23,8 @@ use std::error::Error;
 use std::fmt::Debug;
 use std::str::FromStr;
 
-use bc::Tx;
+use bc::opcodes::{OP_PUSHBYTES_32, OP_RETURN};
+use bc::{Tx, Vout};
 use commit_verify::mpc;
 use strict_encoding::{StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize};
 
@@ -76,8 +77,94 @@ pub trait Proof: Clone + Eq + Debug + StrictSerialize + StrictDeserialize + Stri
     const METHOD: Method;
 
     /// Verification error.
-    type Error: Clone + Error;
+    type Error: Clone + Error + 'static;
 
     /// Verifies DBC proof against the provided transaction.
     fn verify(&self, msg: &mpc::Commitment, tx: &Tx) -> Result<(), Self::Error>;
 }
+
+/// Detects a deterministic bitcoin commitment in the transaction.
+///
+/// First checks the first `OP_RETURN` output of the transaction for the
+/// [`Method::OpretFirst`] commitment, returning the committed value. If it is
+/// absent, checks for the presence of a taproot output, which may contain a
+/// [`Method::TapretFirst`] commitment. Since tapret commitments are tweaked into
+/// the output key, they can be neither extracted nor confirmed without the
+/// proof; in this case only the method is returned.
+///
+/// Returns `None` if the transaction can't contain any commitment.
+pub fn extract_commitment(tx: &Tx) -> Option<(Method, Option<mpc::Commitment>)> {
+    if let Some((_, commitment)) = opret_commitment(tx) {
+        return Some((Method::OpretFirst, Some(commitment)));
+    }
+    if tx.outputs().any(|txout| txout.script_pubkey.is_p2tr()) {
+        return Some((Method::TapretFirst, None));
+    }
+    None
+}
+
+/// Compares two commitment values in constant time.
+#[cfg(all(feature = "subtle", any(feature = "opret", feature = "tapret")))]
+pub(crate) fn commitment_eq<T: crate::CommitmentCtEq>(a: &T, b: &T) -> bool { a.ct_eq(b) }
+
+/// Compares two commitment values; the comparison is done in constant time
+/// only if the `subtle` feature is enabled.
+#[cfg(all(not(feature = "subtle"), any(feature = "opret", feature = "tapret")))]
+pub(crate) fn commitment_eq<T: Eq>(a: &T, b: &T) -> bool { a == b }
+
+/// Returns the output number and the value of the opret commitment, if the
+/// first `OP_RETURN` output of the transaction contains one.
+pub(crate) fn opret_commitment(tx: &Tx) -> Option<(Vout, mpc::Commitment)> {
+    let (vout, txout) = tx.find_output(|txout| txout.script_pubkey.is_op_return())?;
+    let script = txout.script_pubkey.as_slice();
+    if script.len() != 34 || script[0] != OP_RETURN || script[1] != OP_PUSHBYTES_32 {
+        return None;
+    }
+    let mut commitment = [0u8; 32];
+    commitment.copy_from_slice(&script[2..]);
+    Some((vout, mpc::Commitment::from(commitment)))
+}
+
+#[cfg(test)]
+mod test {
+    use bc::{InternalPk, LockTime, ScriptPubkey, TxOut, TxVer};
+
+    use super::*;
+
+    #[test]
+    fn extract() {
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        assert_eq!(extract_commitment(&tx), None);
+
+        tx.push_output(TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64)).unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::op_return(&[0xAA; 16]), 0u64)).unwrap();
+        assert_eq!(extract_commitment(&tx), None);
+
+        let internal_pk = InternalPk::from_str(
+            "c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3",
+        )
+        .unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::p2tr(internal_pk, None), 1000u64)).unwrap();
+        assert_eq!(extract_commitment(&tx), Some((Method::TapretFirst, None)));
+
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_output(TxOut::new(ScriptPubkey::op_return(&[0xBB; 32]), 0u64)).unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::p2tr(internal_pk, None), 1000u64)).unwrap();
+        assert_eq!(
+            extract_commitment(&tx),
+            Some((Method::OpretFirst, Some(mpc::Commitment::from([0xBB; 32]))))
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "opret", feature = "tapret"))]
+    fn commitment_equality() {
+        let a = mpc::Commitment::from([0xAA; 32]);
+        assert!(commitment_eq(&a, &mpc::Commitment::from([0xAA; 32])));
+        assert!(!commitment_eq(&a, &mpc::Commitment::from([0xAB; 32])));
+
+        let script = ScriptPubkey::op_return(&[0xAA; 32]);
+        assert!(commitment_eq(&script, &ScriptPubkey::op_return(&[0xAA; 32])));
+        assert!(!commitment_eq(&script, &ScriptPubkey::op_return(&[0xAA; 31])));
+    }
+}
@@ -0,0 +1,78 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parallel scanning of bitcoin blocks for deterministic bitcoin commitments.
+
+use bc::{Block, Txid, Vout};
+use commit_verify::mpc::Commitment;
+use rayon::prelude::*;
+
+use crate::proof::opret_commitment;
+
+/// Parallel extraction of deterministic bitcoin commitments from blocks.
+pub trait ParScanCommitments {
+    /// Inspects all transactions from the block in parallel, returning
+    /// `opret` commitments found in them.
+    ///
+    /// Only the first `OP_RETURN` output of each transaction is considered,
+    /// as required by the `OpretFirst` protocol.
+    ///
+    /// NB: `tapret` commitments are tweaked into the output key and can't be
+    /// extracted without the proof; they are not reported by this method.
+    /// Since the on-chain commitment is a multi-protocol commitment root, the
+    /// returned values are not filtered by a protocol id.
+    fn par_scan_commitments(&self) -> Vec<(Txid, Vout, Commitment)>;
+}
+
+impl ParScanCommitments for Block {
+    fn par_scan_commitments(&self) -> Vec<(Txid, Vout, Commitment)> {
+        self.transactions
+            .par_iter()
+            .filter_map(|tx| {
+                opret_commitment(tx).map(|(vout, commitment)| (tx.txid(), vout, commitment))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::{LockTime, ScriptPubkey, Tx, TxOut, TxVer};
+
+    use super::*;
+
+    #[test]
+    fn scan_opret() {
+        let mut block = Block {
+            header: strict_dumb!(),
+            transactions: none!(),
+        };
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        block.transactions.push(tx.clone()).unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64)).unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::op_return(&[0xAA; 32]), 0u64)).unwrap();
+        tx.push_output(TxOut::new(ScriptPubkey::op_return(&[0xBB; 32]), 0u64)).unwrap();
+        block.transactions.push(tx.clone()).unwrap();
+
+        let found = block.par_scan_commitments();
+        assert_eq!(found, vec![(tx.txid(), Vout::from_u32(1), Commitment::from([0xAA; 32]))]);
+    }
+}
@@ -67,15 +67,20 @@ mod txout;
 mod spk;
 mod xonlypk;
 
-use bc::{InternalPk, IntoTapHash, LeafScript, ScriptPubkey, TapBranchHash, TapNodeHash, Tx};
+use bc::{
+    InternalPk, IntoTapHash, LeafScript, ScriptPubkey, TapBranchHash, TapMerklePath, TapNodeHash,
+    Tx, VarInt, WeightUnits, TXIN_BASE_SIZE,
+};
 use commit_verify::mpc::Commitment;
-use commit_verify::{CommitmentProtocol, ConvolveCommitProof, ConvolveVerifyError};
-use strict_encoding::{StrictDeserialize, StrictSerialize};
+use commit_verify::{CommitmentProtocol, ConvolveCommit, ConvolveVerifyError};
+use strict_encoding::{
+    DecodeError, ReadStruct, StrictDecode, StrictDeserialize, StrictSerialize, TypedRead,
+};
 pub use tapscript::{TapretCommitment, TAPRET_SCRIPT_COMMITMENT_PREFIX};
 pub use tx::TapretError;
-pub use xonlypk::TapretKeyError;
+pub use xonlypk::{TapretKeyError, TapretVerify};
 
-use crate::proof::Method;
+use crate::proof::{commitment_eq, Method};
 use crate::{Proof, LIB_NAME_BPCORE};
 
 /// Marker non-instantiable enum defining LNPBP-12 taproot OP_RETURN (`tapret`)
@@ -101,7 +106,7 @@ pub enum TapretPathError {
 /// [`TapretNodePartner::RightBranch`] to ensure correct consensus ordering of
 /// the child elements.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[derive(StrictType, StrictDumb, StrictEncode)]
 #[strict_type(lib = LIB_NAME_BPCORE)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
 #[display("{left_node_hash}:{right_node_hash}")]
@@ -137,26 +142,23 @@ impl TapretRightBranch {
     }
 }
 
-/*
 impl StrictDecode for TapretRightBranch {
-    fn strict_decode<D: Read>(
-        mut d: D,
-    ) -> Result<Self, strict_encoding::Error> {
-        let left_node_hash = StrictDecode::strict_decode(&mut d)?;
-        let right_node_hash = StrictDecode::strict_decode(d)?;
-        if left_node_hash > right_node_hash {
-            Err(strict_encoding::Error::DataIntegrityError(s!(
-                "non-cosensus ordering of hashes in TapretRightBranch"
-            )))
-        } else {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_struct(|r| {
+            let left_node_hash: TapNodeHash = r.read_field(fname!("leftNodeHash"))?;
+            let right_node_hash: TapNodeHash = r.read_field(fname!("rightNodeHash"))?;
+            if left_node_hash > right_node_hash {
+                return Err(DecodeError::DataIntegrityError(s!(
+                    "non-consensus ordering of hashes in TapretRightBranch"
+                )));
+            }
             Ok(TapretRightBranch {
                 left_node_hash,
                 right_node_hash,
             })
-        }
+        })
     }
 }
- */
 
 /// Information proving step of a tapret path in determined way within a given
 /// tap tree.
@@ -357,6 +359,90 @@ impl TapretProof {
         let merkle_root = self.path_proof.original_merkle_root();
         ScriptPubkey::p2tr(self.internal_pk, merkle_root)
     }
+
+    /// Computes scriptPubkey of the output committing to the `msg` with this
+    /// proof, i.e. [`Self::original_pubkey_script`] convolved with the message.
+    ///
+    /// This allows locating the committed output in a transaction by comparing
+    /// its scriptPubkey. NB: the commitment is valid only if the output is the
+    /// first taproot output of the transaction, which is checked by the
+    /// transaction-level verification.
+    pub fn committed_script_pubkey(
+        &self,
+        msg: &Commitment,
+    ) -> Result<ScriptPubkey, TapretKeyError> {
+        let (output_key, _) = self.internal_pk.convolve_commit(&self.path_proof, msg)?;
+        Ok(ScriptPubkey::p2tr_tweaked(output_key))
+    }
+
+    /// Verifies the internal key used by the taproot output.
+    ///
+    /// If `expected` key is provided, checks that the proof internal key
+    /// matches it (for instance, protocols may require the use of the
+    /// [`InternalPk::nums`] point to guarantee there is no hidden key path
+    /// spending). Otherwise, checks that the internal key is not the BIP-341
+    /// NUMS point, i.e. that the output has an intended key path spending.
+    pub fn verify_internal_pk(&self, expected: Option<InternalPk>) -> bool {
+        match expected {
+            Some(expected) => self.internal_pk == expected,
+            None => !self.internal_pk.is_nums(),
+        }
+    }
+
+    /// Checks that the script tree can't contain a tapret commitment other
+    /// than the one proven by this proof.
+    ///
+    /// Tapret path proofs currently carry only the partner node at the first
+    /// level of the script tree, so the check covers that single level with
+    /// [`TapretNodePartner::check_no_commitment`]. Proofs without a partner
+    /// node (i.e. when the commitment is the only script in the tree) always
+    /// pass the check.
+    ///
+    /// NB: proofs constructed with [`TapretPathProof::with`] always pass the
+    /// check; it is required for the proofs coming from other sources.
+    pub fn check_no_alternative_commitment(&self) -> bool { self.path_proof.check_no_commitment() }
+
+    /// Estimates weight of a transaction input spending the tapret-committed
+    /// output via the key path.
+    ///
+    /// Assumes a 64-byte BIP-340 signature (i.e. the default sighash type) and
+    /// no annex. The segwit marker and flag are accounted per transaction and
+    /// are not included.
+    pub fn spend_weight_key_path(&self) -> WeightUnits {
+        WeightUnits::no_discount(TXIN_BASE_SIZE) + WeightUnits::witness_discount(1 + 1 + 64)
+    }
+
+    /// Estimates weight of a transaction input spending the tapret-committed
+    /// output via the script path using the `leaf` script, which has the
+    /// `merkle_path` in the original script tree (i.e. the tree before the
+    /// tapret commitment was added).
+    ///
+    /// The tapret commitment adds a level to the script tree, so the control
+    /// block has one more merkle path element than `merkle_path`. The weight
+    /// of the witness stack elements satisfying the leaf script is not
+    /// included and must be added by the caller; their number is assumed to
+    /// be less than 252.
+    ///
+    /// Returns `None` if the proof doesn't have a partner node, meaning that
+    /// the script tree consists of the unspendable commitment leaf only, or if
+    /// the control block would exceed the maximum taproot tree depth.
+    pub fn spend_weight_script_path(
+        &self,
+        leaf: &LeafScript,
+        merkle_path: &TapMerklePath,
+    ) -> Option<WeightUnits> {
+        self.path_proof.partner_node.as_ref()?;
+        if merkle_path.len() >= 128 {
+            return None;
+        }
+        let control_block_len = 33 + 32 * (merkle_path.len() + 1);
+        let witness_len = 1
+            + leaf.script.len_var_int().len()
+            + leaf.script.len()
+            + VarInt::with(control_block_len).len()
+            + control_block_len;
+        Some(WeightUnits::no_discount(TXIN_BASE_SIZE) + WeightUnits::witness_discount(witness_len))
+    }
 }
 
 impl Proof for TapretProof {
@@ -365,6 +451,191 @@ impl Proof for TapretProof {
     const METHOD: Method = Method::TapretFirst;
 
     fn verify(&self, msg: &Commitment, tx: &Tx) -> Result<(), ConvolveVerifyError> {
-        ConvolveCommitProof::<_, Tx, _>::verify(self, msg, tx)
+        let Some((_, txout)) = tx.find_output(|txout| txout.script_pubkey.is_p2tr()) else {
+            return Err(ConvolveVerifyError::ImpossibleMessage);
+        };
+        let script_pubkey = self
+            .committed_script_pubkey(msg)
+            .map_err(|_| ConvolveVerifyError::ImpossibleMessage)?;
+        if !commitment_eq(&script_pubkey, &txout.script_pubkey) {
+            return Err(ConvolveVerifyError::CommitmentMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fmt::Debug;
+
+    use amplify::confinement::{Confined, U16};
+    use bc::{LockTime, TapScript, TxOut, TxVer};
+
+    use super::*;
+
+    const INTERNAL_PK: [u8; 32] = [
+        0xc5, 0xf9, 0x34, 0x79, 0x09, 0x3e, 0x2b, 0x8f, 0x72, 0x4a, 0x79, 0x84, 0x4c, 0xc1, 0x09,
+        0x28, 0xdd, 0x44, 0xe9, 0xa3, 0x90, 0xb5, 0x39, 0x84, 0x3f, 0xb8, 0x3f, 0xbf, 0x84, 0x27,
+        0x23, 0xf3,
+    ];
+
+    fn strict_roundtrip<T>(val: &T)
+    where T: StrictSerialize + StrictDeserialize + Eq + Debug {
+        let data = val.to_strict_serialized::<U16>().unwrap();
+        assert_eq!(&T::from_strict_serialized::<U16>(data).unwrap(), val);
+    }
+
+    #[test]
+    fn strict_roundtrips() {
+        let internal_pk = InternalPk::from_byte_array(INTERNAL_PK).unwrap();
+        let a = TapNodeHash::from([0x01; 32]);
+        let b = TapNodeHash::from([0xFE; 32]);
+        let leaf = LeafScript::from_tap_script(TapScript::from_unsafe(vec![0x51; 10]));
+
+        // Hashes provided in non-consensus order must be stored ordered
+        let branch = TapretRightBranch::with(b, a);
+        assert_eq!(branch.left_node_hash(), a);
+        assert_eq!(branch.right_node_hash(), b);
+
+        let partners = [
+            TapretNodePartner::LeftNode(a),
+            TapretNodePartner::RightLeaf(leaf),
+            TapretNodePartner::RightBranch(branch),
+            TapretNodePartner::right_branch(a, a),
+        ];
+        strict_roundtrip(&TapretPathProof::root(0));
+        for (nonce, partner) in partners.into_iter().enumerate() {
+            let path_proof = TapretPathProof::with(partner, nonce as u8).unwrap();
+            strict_roundtrip(&path_proof);
+            strict_roundtrip(&TapretProof {
+                path_proof,
+                internal_pk,
+            });
+        }
+    }
+
+    #[test]
+    fn committed_script_pubkey() {
+        let internal_pk = InternalPk::from_byte_array(INTERNAL_PK).unwrap();
+        let proof = TapretProof {
+            path_proof: TapretPathProof::root(0),
+            internal_pk,
+        };
+        let msg = Commitment::from([0xAB; 32]);
+
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        tx.push_output(TxOut::new(proof.original_pubkey_script(), 1000u64)).unwrap();
+        let (tx, _) = tx.convolve_commit(&proof, &msg).unwrap();
+
+        let script_pubkey = proof.committed_script_pubkey(&msg).unwrap();
+        assert!(script_pubkey.is_p2tr());
+        assert_eq!(tx.outputs[0].script_pubkey, script_pubkey);
+        assert_ne!(script_pubkey, proof.original_pubkey_script());
+        assert!(Proof::verify(&proof, &msg, &tx).is_ok());
+        assert_eq!(
+            Proof::verify(&proof, &Commitment::from([0xAC; 32]), &tx),
+            Err(ConvolveVerifyError::CommitmentMismatch)
+        );
+        assert_eq!(
+            Proof::verify(&proof, &msg, &Tx::new(TxVer::V2, LockTime::ZERO)),
+            Err(ConvolveVerifyError::ImpossibleMessage)
+        );
+    }
+
+    #[test]
+    fn no_alternative_commitment() {
+        let internal_pk = InternalPk::from_byte_array(INTERNAL_PK).unwrap();
+        let proof = |partner_node: Option<TapretNodePartner>| TapretProof {
+            path_proof: TapretPathProof {
+                partner_node,
+                nonce: 0,
+            },
+            internal_pk,
+        };
+        let mut commitment_script = TAPRET_SCRIPT_COMMITMENT_PREFIX.to_vec();
+        commitment_script.extend([0xAA; 33]);
+        let commitment_leaf =
+            LeafScript::from_tap_script(TapScript::from_unsafe(commitment_script));
+        let mut prefixed_hash = [0xFF; 32];
+        prefixed_hash[..31].copy_from_slice(&TAPRET_SCRIPT_COMMITMENT_PREFIX);
+
+        assert!(proof(None).check_no_alternative_commitment());
+        assert!(proof(Some(TapretNodePartner::LeftNode(prefixed_hash.into())))
+            .check_no_alternative_commitment());
+        assert!(proof(Some(TapretNodePartner::RightLeaf(LeafScript::from_tap_script(
+            TapScript::from_unsafe(vec![0x51; 64])
+        ))))
+        .check_no_alternative_commitment());
+        assert!(proof(Some(TapretNodePartner::right_branch(
+            [0x01; 32].into(),
+            prefixed_hash.into()
+        )))
+        .check_no_alternative_commitment());
+
+        assert!(!proof(Some(TapretNodePartner::RightLeaf(commitment_leaf.clone())))
+            .check_no_alternative_commitment());
+        assert!(!proof(Some(TapretNodePartner::right_branch(
+            prefixed_hash.into(),
+            [0xFF; 32].into()
+        )))
+        .check_no_alternative_commitment());
+        assert_eq!(
+            TapretPathProof::with(TapretNodePartner::RightLeaf(commitment_leaf.clone()), 0),
+            Err(TapretPathError::InvalidNodePartner(TapretNodePartner::RightLeaf(commitment_leaf)))
+        );
+    }
+
+    #[test]
+    fn right_branch_misordered_decode() {
+        let path_proof = TapretPathProof::with(
+            TapretNodePartner::right_branch([0x01; 32].into(), [0xFE; 32].into()),
+            0,
+        )
+        .unwrap();
+        let mut data = path_proof.to_strict_serialized::<U16>().unwrap().release();
+        // Swap the hashes, which directly follow the partner option and the enum tags
+        let hashes = data.len() - 1 - 64;
+        let (left, right) = data[hashes..hashes + 64].split_at_mut(32);
+        left.swap_with_slice(right);
+        assert_eq!(&data[hashes..hashes + 32], &[0xFE; 32]);
+
+        let err = TapretPathProof::from_strict_serialized::<U16>(Confined::try_from(data).unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            strict_encoding::DeserializeError::Decode(DecodeError::DataIntegrityError(_))
+        ));
+    }
+
+    #[test]
+    fn spend_weights() {
+        let internal_pk = InternalPk::from_byte_array(INTERNAL_PK).unwrap();
+        let leaf = LeafScript::from_tap_script(TapScript::from_unsafe(vec![0x51; 10]));
+        let merkle_path =
+            |depth: usize| TapMerklePath::try_from(vec![TapBranchHash::from([0x01; 32]); depth]);
+
+        let proof = TapretProof {
+            path_proof: TapretPathProof::root(0),
+            internal_pk,
+        };
+        // 41 non-witness bytes and witness with a single 64-byte signature
+        assert_eq!(proof.spend_weight_key_path().to_u32(), 41 * 4 + 66);
+        assert_eq!(proof.spend_weight_script_path(&leaf, &TapMerklePath::default()), None);
+
+        let proof = TapretProof {
+            path_proof: TapretPathProof::with(TapretNodePartner::RightLeaf(leaf.clone()), 0)
+                .unwrap(),
+            internal_pk,
+        };
+        // item count, script with its length, control block with its length
+        assert_eq!(
+            proof.spend_weight_script_path(&leaf, &TapMerklePath::default()).unwrap().to_u32(),
+            41 * 4 + 1 + 11 + 1 + 65
+        );
+        assert_eq!(
+            proof.spend_weight_script_path(&leaf, &merkle_path(7).unwrap()).unwrap().to_u32(),
+            41 * 4 + 1 + 11 + 3 + 33 + 32 * 8
+        );
+        assert_eq!(proof.spend_weight_script_path(&leaf, &merkle_path(128).unwrap()), None);
     }
 }
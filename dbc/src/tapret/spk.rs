@@ -41,11 +41,7 @@ impl ConvolveCommit<mpc::Commitment, TapretProof, TapretFirst> for ScriptPubkey
         supplement: &TapretProof,
         msg: &mpc::Commitment,
     ) -> Result<(ScriptPubkey, TapretProof), Self::CommitError> {
-        let (output_key, _) =
-            supplement.internal_pk.convolve_commit(&supplement.path_proof, msg)?;
-
-        let script_pubkey = ScriptPubkey::p2tr_tweaked(output_key);
-
+        let script_pubkey = supplement.committed_script_pubkey(msg)?;
         Ok((script_pubkey, supplement.clone()))
     }
 }
@@ -40,6 +40,10 @@ pub const TAPRET_SCRIPT_COMMITMENT_PREFIX: [u8; 31] = [
 ];
 
 /// Information about tapret commitment.
+///
+/// The commitment is serialized as 33 bytes: 32-byte MPC commitment followed
+/// by a single-byte nonce. String representation (see [`Display`] and
+/// [`FromStr`]) is a Base85 encoding of these bytes.
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BPCORE)]
@@ -90,8 +94,42 @@ impl FromStr for TapretCommitment {
 impl TapretCommitment {
     /// Constructs information about tapret commitment.
     pub fn with(mpc: mpc::Commitment, nonce: u8) -> Self { Self { mpc, nonce } }
+
+    /// Returns LNPBP-4 multi-protocol commitment.
+    #[inline]
+    pub fn mpc_commitment(&self) -> mpc::Commitment { self.mpc }
+
+    /// Returns nonce used to put the commitment into the correct side of the
+    /// tree.
+    #[inline]
+    pub fn nonce(&self) -> u8 { self.nonce }
+
+    /// Extracts tapret commitment from a tapret script, which must be
+    /// produced by [`TapScript::commit`] (see [`TAPRET_SCRIPT_COMMITMENT_PREFIX`]
+    /// for the script layout).
+    ///
+    /// Returns `None` if the script is not a tapret commitment script.
+    pub fn from_tap_script(script: &TapScript) -> Option<Self> {
+        if script.len() != TAPRET_SCRIPT_COMMITMENT_PREFIX.len() + 33
+            || script[..31] != TAPRET_SCRIPT_COMMITMENT_PREFIX[..]
+        {
+            return None;
+        }
+        let mut data = [0u8; 33];
+        data.copy_from_slice(&script[31..]);
+        Some(Self::from(data))
+    }
 }
 
+/// Constructs tapret commitment script, which is exactly 64 bytes long and has
+/// the following layout:
+/// - 31-byte [`TAPRET_SCRIPT_COMMITMENT_PREFIX`]: 29 `OP_RESERVED` opcodes, followed by `OP_RETURN`
+///   and `OP_PUSHBYTES_33`;
+/// - 32-byte MPC commitment;
+/// - 1-byte nonce.
+///
+/// The commitment can be extracted back from the script with
+/// [`TapretCommitment::from_tap_script`].
 impl CommitVerify<TapretCommitment, TapretFirst> for TapScript {
     /// Tapret script consists of 29 `OP_RESERVED` pushes, followed by
     /// `OP_RETURN`, `OP_PUSHBYTES_33` and serialized commitment data (MPC
@@ -173,6 +211,19 @@ mod test {
         assert_eq!(&script[31..63], commitment.mpc.as_slice());
     }
 
+    #[test]
+    pub fn commitment_extraction() {
+        let commitment = commitment();
+        let script = TapScript::commit(&commitment);
+        assert_eq!(script.len(), 64);
+        assert_eq!(TapretCommitment::from_tap_script(&script), Some(commitment));
+
+        let mut script = script.to_vec();
+        script[0] = 0x51;
+        assert_eq!(TapretCommitment::from_tap_script(&TapScript::from_unsafe(script)), None);
+        assert_eq!(TapretCommitment::from_tap_script(&TapScript::new()), None);
+    }
+
     #[test]
     pub fn tapret_commitment_baid64() {
         let commitment = commitment();
@@ -180,4 +231,13 @@ mod test {
         assert_eq!(s, "k#7JerF92P=PEN7cf&`GWfS*?rIEdfEup1%zausI2m");
         assert_eq!(Ok(commitment.clone()), TapretCommitment::from_str(&s));
     }
+
+    #[test]
+    pub fn accessors() {
+        let commitment = commitment();
+        let same = TapretCommitment::with(commitment.mpc_commitment(), commitment.nonce());
+        assert_eq!(same, commitment);
+        assert_eq!(same.nonce(), 8);
+        assert_eq!(TapretCommitment::from(<[u8; 33]>::try_from(same.to_vec()).unwrap()), same);
+    }
 }
@@ -38,6 +38,27 @@ pub enum TapretKeyError {
     IncorrectOrdering(TapretNodePartner, TapLeafHash),
 }
 
+/// Verification of tapret commitments against a known taproot output key.
+///
+/// Implemented for [`OutputPk`], which is a foreign type to this crate, hence
+/// the extension trait.
+pub trait TapretVerify {
+    /// Verifies that the output key contains tapret commitment to `msg`.
+    ///
+    /// Reconstructs the expected tweaked output key from the
+    /// [`TapretProof::internal_pk`], the tapscript committing to `msg` and
+    /// the path proof, and compares it with `self`. Returns `false` if the
+    /// keys do not match or if the path proof is invalid (i.e. the partner
+    /// node may contain an alternative commitment or has incorrect ordering).
+    fn verify_tapret(&self, proof: &TapretProof, msg: &mpc::Commitment) -> bool;
+}
+
+impl TapretVerify for OutputPk {
+    fn verify_tapret(&self, proof: &TapretProof, msg: &mpc::Commitment) -> bool {
+        ConvolveCommitProof::<_, InternalPk, TapretFirst>::verify(proof, msg, self).is_ok()
+    }
+}
+
 impl ConvolveCommitProof<mpc::Commitment, InternalPk, TapretFirst> for TapretProof {
     type Suppl = TapretPathProof;
 
@@ -178,4 +199,31 @@ mod test {
         )
         .unwrap();
     }
+
+    #[test]
+    fn verify_tapret() {
+        let internal_pk = InternalPk::from_str(
+            "c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3",
+        )
+        .unwrap();
+        let msg = mpc::Commitment::from([8u8; 32]);
+        let path_proof = TapretPathProof::with(
+            TapretNodePartner::RightLeaf(LeafScript::from_tap_script(default!())),
+            1,
+        )
+        .unwrap();
+
+        let (outer_key, proof) = internal_pk.convolve_commit(&path_proof, &msg).unwrap();
+        assert!(outer_key.verify_tapret(&proof, &msg));
+
+        let other_msg = mpc::Commitment::from([9u8; 32]);
+        assert!(!outer_key.verify_tapret(&proof, &other_msg));
+
+        let (other_key, _) = internal_pk.to_output_pk(None);
+        assert!(!other_key.verify_tapret(&proof, &msg));
+
+        let mut bad_nonce = proof.clone();
+        bad_nonce.path_proof = TapretPathProof::root(1);
+        assert!(!outer_key.verify_tapret(&bad_nonce, &msg));
+    }
 }
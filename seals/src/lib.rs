@@ -46,5 +46,6 @@ extern crate serde;
 mod txout;
 
 pub use txout::{
-    mmb, mpc, Anchor, AnchorError, AnchorMergeError, Noise, TxoSeal, TxoSealDef, TxoSealExt,
+    mmb, mpc, Anchor, AnchorError, AnchorMergeError, Noise, TxoSeal, TxoSealDef, TxoSealError,
+    TxoSealExt, TxoSealParseError, TxoSealVerifyError,
 };
@@ -24,14 +24,17 @@
 
 use core::cmp::Ordering;
 use core::error::Error;
-use core::fmt::Debug;
+use core::fmt::{self, Debug, Display, Formatter};
 use core::marker::PhantomData;
+use core::str::FromStr;
 
-use amplify::{ByteArray, Bytes, Bytes32};
-use bc::{Outpoint, Tx, Txid, Vout};
+use amplify::hex::{self, FromHex, ToHex};
+use amplify::{ByteArray, Bytes, Bytes32, FromSliceError, Wrapper};
+use bc::{Outpoint, OutpointParseError, ScriptPubkey, Tx, Txid, Vout};
 use commit_verify::{CommitId, DigestExt, ReservedBytes, Sha256, StrictHash};
+use dbc::Method;
 use single_use_seals::{ClientSideWitness, PublishedWitness, SealWitness, SingleUseSeal};
-use strict_encoding::StrictDumb;
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -39,6 +42,49 @@ use strict_encoding::StrictDumb;
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Noise(Bytes<40>);
 
+impl Display for Noise {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&self.0.to_hex()) }
+}
+
+impl FromStr for Noise {
+    type Err = hex::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Bytes::<40>::from_hex(s).map(Self) }
+}
+
+/// Implements uniform conversions between a 32-byte identifier newtype and
+/// byte arrays and slices.
+macro_rules! impl_byte_array_conversions {
+    ($ty:ty) => {
+        impl $ty {
+            #[inline]
+            pub fn from_byte_array(bytes: [u8; 32]) -> Self { Self(Bytes32::from_array(bytes)) }
+
+            #[inline]
+            pub fn to_byte_array(&self) -> [u8; 32] { self.0.to_byte_array() }
+        }
+
+        impl AsRef<[u8; 32]> for $ty {
+            #[inline]
+            fn as_ref(&self) -> &[u8; 32] { self.0.as_inner() }
+        }
+
+        impl From<$ty> for [u8; 32] {
+            #[inline]
+            fn from(value: $ty) -> Self { value.to_byte_array() }
+        }
+
+        impl TryFrom<&[u8]> for $ty {
+            type Error = FromSliceError;
+
+            #[inline]
+            fn try_from(slice: &[u8]) -> Result<Self, Self::Error> {
+                Bytes32::copy_from_slice(slice).map(Self)
+            }
+        }
+    };
+}
+
 pub mod mmb {
     use amplify::confinement::SmallOrdMap;
     use commit_verify::{CommitmentId, DigestExt, Sha256};
@@ -69,6 +115,30 @@ pub mod mmb {
     impl CommitmentId for Commitment {
         const TAG: &'static str = "urn:lnp-bp:mmb:bundle#2024-11-18";
     }
+
+    impl_byte_array_conversions!(Message);
+    impl_byte_array_conversions!(Commitment);
+
+    /// SHA256 hash of the [`Commitment`] tag (see [`CommitmentId::TAG`]),
+    /// which is used as a double prefix of the tagged hash.
+    pub const COMMITMENT_TAG_HASH: [u8; 32] = [
+        0x6d, 0xaa, 0xba, 0x37, 0xc3, 0x0d, 0xc0, 0x8c, 0x8d, 0x09, 0x5c, 0xc0, 0x6a, 0xac, 0x1b,
+        0xa5, 0xd7, 0xbc, 0x23, 0xba, 0x55, 0xa0, 0xd3, 0xc6, 0x66, 0x31, 0x98, 0x6f, 0x2f, 0x34,
+        0xc3, 0x0e,
+    ];
+
+    /// Constructs hash engine for [`Commitment`], already fed with the tagged
+    /// hash prefix from [`COMMITMENT_TAG_HASH`].
+    ///
+    /// The prefix fills exactly one SHA256 block, so the engine holds the
+    /// tagged hash midstate and can be cloned for committing to many bundles.
+    pub fn commitment_engine() -> Sha256 {
+        let mut engine = Sha256::default();
+        engine.input_raw(&COMMITMENT_TAG_HASH);
+        engine.input_raw(&COMMITMENT_TAG_HASH);
+        engine
+    }
+
     impl From<Sha256> for Commitment {
         fn from(hasher: Sha256) -> Self { hasher.finish().into() }
     }
@@ -77,8 +147,8 @@ pub mod mmb {
         fn from(msg: Commitment) -> Self { mpc::Message::from_byte_array(msg.to_byte_array()) }
     }
 
-    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
-    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+    #[derive(StrictType, StrictEncode, StrictDecode)]
     #[strict_type(lib = dbc::LIB_NAME_BPCORE)]
     #[derive(CommitEncode)]
     #[commit_encode(strategy = strict, id = Commitment)]
@@ -87,12 +157,62 @@ pub mod mmb {
         pub map: SmallOrdMap<u32, Message>,
     }
 
+    /// Errors constructing multi-message bundle proofs.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+    #[display(doc_comments)]
+    pub enum MmbError {
+        /// bundle proof can't contain more than 2^16-1 messages.
+        TooManyMessages,
+
+        /// message is assigned to input {index}, while the transaction has
+        /// only {inputs} inputs.
+        InputOutOfRange { index: u32, inputs: usize },
+
+        /// multiple messages are assigned to input {0}.
+        DuplicateInput(u32),
+    }
+
     impl BundleProof {
+        #[inline]
+        pub fn new() -> Self { Self::default() }
+
+        /// Constructs bundle proof for the transaction from messages assigned
+        /// to its inputs, checking that all input indexes are valid and that
+        /// each input is assigned at most one message.
+        pub fn with_tx_messages(
+            tx: &Tx,
+            messages: impl IntoIterator<Item = (u32, Message)>,
+        ) -> Result<Self, MmbError> {
+            let inputs = tx.inputs.len();
+            let mut proof = Self::new();
+            for (index, msg) in messages {
+                if index as usize >= inputs {
+                    return Err(MmbError::InputOutOfRange { index, inputs });
+                }
+                if proof.try_insert(index, msg)?.is_some() {
+                    return Err(MmbError::DuplicateInput(index));
+                }
+            }
+            Ok(proof)
+        }
+
+        /// Assigns a message to a transaction input, returning the message
+        /// previously assigned to the same input, if any.
+        ///
+        /// Errors with [`MmbError::TooManyMessages`] if the proof already
+        /// contains the maximal number of messages.
+        pub fn try_insert(
+            &mut self,
+            input_index: u32,
+            msg: Message,
+        ) -> Result<Option<Message>, MmbError> {
+            self.map.insert(input_index, msg).map_err(|_| MmbError::TooManyMessages)
+        }
+
         pub fn verify(&self, seal: Outpoint, msg: Message, tx: &Tx) -> bool {
-            let Some(input_index) = tx.inputs().position(|input| input.prev_output == seal) else {
-                return false;
-            };
-            let Ok(input_index) = u32::try_from(input_index) else {
+            let Some((input_index, _)) =
+                tx.inputs_with_index().find(|(_, input)| input.prev_output == seal)
+            else {
                 return false;
             };
             let Some(expected) = self.map.get(&input_index) else {
@@ -151,6 +271,76 @@ pub mod mpc {
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
     pub struct MessageMap(MediumOrdMap<ProtocolId, MessageSource>);
 
+    /// Errors adding messages to [`MessageMap`].
+    #[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+    #[display(doc_comments)]
+    pub enum MessageMapError {
+        /// message map already contains a different message for the protocol
+        /// {0}.
+        DuplicateProtocol(ProtocolId),
+
+        /// message map can't contain more than 2^24 protocols.
+        TooManyProtocols,
+    }
+
+    impl MessageMap {
+        #[inline]
+        pub fn new() -> Self { Self::default() }
+
+        #[inline]
+        pub fn len(&self) -> usize { self.0.len() }
+
+        #[inline]
+        pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+        #[inline]
+        pub fn get(&self, protocol_id: &ProtocolId) -> Option<&MessageSource> {
+            self.0.get(protocol_id)
+        }
+
+        /// Adds message source for a protocol.
+        ///
+        /// Unlike map insertion, never overwrites existing data: if the map
+        /// already contains a different message source for the protocol, errors
+        /// with [`MessageMapError::DuplicateProtocol`]. Repeated insertion of
+        /// the same source is a no-op.
+        pub fn insert(
+            &mut self,
+            protocol_id: ProtocolId,
+            source: impl Into<MessageSource>,
+        ) -> Result<(), MessageMapError> {
+            let source = source.into();
+            match self.0.get(&protocol_id) {
+                Some(existing) if *existing == source => Ok(()),
+                Some(_) => Err(MessageMapError::DuplicateProtocol(protocol_id)),
+                None => self
+                    .0
+                    .insert(protocol_id, source)
+                    .map(|_| ())
+                    .map_err(|_| MessageMapError::TooManyProtocols),
+            }
+        }
+
+        /// Adds all messages from the `other` map, following the rules of
+        /// [`MessageMap::insert`].
+        ///
+        /// The operation is atomic: in case of an error the map is left
+        /// unchanged.
+        pub fn merge(&mut self, other: MessageMap) -> Result<(), MessageMapError> {
+            for (protocol_id, source) in &other.0 {
+                if self.0.get(protocol_id).is_some_and(|existing| existing != source) {
+                    return Err(MessageMapError::DuplicateProtocol(*protocol_id));
+                }
+            }
+            let mut merged = self.clone();
+            for (protocol_id, source) in other.0 {
+                merged.insert(protocol_id, source)?;
+            }
+            *self = merged;
+            Ok(())
+        }
+    }
+
     #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
     #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
     #[strict_type(lib = dbc::LIB_NAME_BPCORE)]
@@ -196,6 +386,9 @@ pub struct Anchor<D: dbc::Proof> {
     pub fallback_proof: ReservedBytes<1>,
 }
 
+impl<D: dbc::Proof> StrictSerialize for Anchor<D> {}
+impl<D: dbc::Proof> StrictDeserialize for Anchor<D> {}
+
 impl<D: dbc::Proof> Anchor<D> {
     // TODO: Change when the fallback proofs are ready
     pub fn is_fallback(&self) -> bool { false }
@@ -209,12 +402,17 @@ pub struct Proof<D: dbc::Proof> {
     pub dbc_proof: D,
 }
 
+/// Secondary component of a seal definition: either noise hiding the primary
+/// outpoint or a fallback outpoint.
+///
+/// In human-readable serde formats the variants are tagged with a `type`
+/// field, like `{"type":"fallback","outpoint":"<txid>:<vout>"}` or
+/// `{"type":"noise","value":"<hex>"}`.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = dbc::LIB_NAME_BPCORE, tags = custom)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
 pub enum TxoSealExt {
-    #[display("~")]
+    #[display("~{0}")]
     #[strict_type(tag = 0)]
     Noise(Noise),
 
@@ -227,6 +425,78 @@ impl StrictDumb for TxoSealExt {
     fn strict_dumb() -> Self { TxoSealExt::Noise(Noise::from(Bytes::from_byte_array([0u8; 40]))) }
 }
 
+/// Parses secondary seal data: either a noise value in hex prefixed with `~`,
+/// or a fallback outpoint in the `<txid>:<vout>` form.
+impl FromStr for TxoSealExt {
+    type Err = TxoSealParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('~') {
+            Some(noise) => Ok(TxoSealExt::Noise(noise.parse()?)),
+            None => Ok(TxoSealExt::Fallback(s.parse()?)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod _serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    /// Human-readable representation of [`TxoSealExt`] tagged with its type.
+    #[derive(Serialize, Deserialize)]
+    #[serde(tag = "type", rename_all = "camelCase")]
+    enum TaggedExt {
+        Noise { value: Noise },
+        Fallback { outpoint: Outpoint },
+    }
+
+    /// Compact representation of [`TxoSealExt`] for binary formats, which
+    /// may not support internally tagged enums.
+    #[derive(Serialize, Deserialize)]
+    enum CompactExt {
+        Noise(Noise),
+        Fallback(Outpoint),
+    }
+
+    impl Serialize for TxoSealExt {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+            if serializer.is_human_readable() {
+                match *self {
+                    TxoSealExt::Noise(value) => TaggedExt::Noise { value },
+                    TxoSealExt::Fallback(outpoint) => TaggedExt::Fallback { outpoint },
+                }
+                .serialize(serializer)
+            } else {
+                match *self {
+                    TxoSealExt::Noise(noise) => CompactExt::Noise(noise),
+                    TxoSealExt::Fallback(outpoint) => CompactExt::Fallback(outpoint),
+                }
+                .serialize(serializer)
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for TxoSealExt {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+            if deserializer.is_human_readable() {
+                Ok(match TaggedExt::deserialize(deserializer)? {
+                    TaggedExt::Noise { value } => TxoSealExt::Noise(value),
+                    TaggedExt::Fallback { outpoint } => TxoSealExt::Fallback(outpoint),
+                })
+            } else {
+                Ok(match CompactExt::deserialize(deserializer)? {
+                    CompactExt::Noise(noise) => TxoSealExt::Noise(noise),
+                    CompactExt::Fallback(outpoint) => TxoSealExt::Fallback(outpoint),
+                })
+            }
+        }
+    }
+}
+
 /// Seal definition which is not specific to a used single-use seal protocol.
 ///
 /// Seals of this type can't be used in seal validation or in closing seals, and are used for
@@ -243,6 +513,22 @@ pub struct TxoSealDef {
     pub secondary: TxoSealExt,
 }
 
+/// Parses seal definition from the `<primary>/<secondary>` form produced by
+/// its `Display` implementation, where `<primary>` is the seal outpoint and
+/// `<secondary>` is parsed by [`TxoSealExt::from_str`].
+impl FromStr for TxoSealDef {
+    type Err = TxoSealParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (primary, secondary) =
+            s.split_once('/').ok_or_else(|| TxoSealParseError::NoSecondary(s.to_owned()))?;
+        Ok(TxoSealDef {
+            primary: primary.parse()?,
+            secondary: secondary.parse()?,
+        })
+    }
+}
+
 impl<D: dbc::Proof> From<TxoSeal<D>> for TxoSealDef {
     fn from(seal: TxoSeal<D>) -> Self {
         TxoSealDef {
@@ -265,6 +551,22 @@ pub struct TxoSeal<D: dbc::Proof> {
     _phantom: PhantomData<D>,
 }
 
+/// Parses seal from the same form as [`TxoSealDef`].
+///
+/// # Errors
+///
+/// In addition to the parsing errors of [`TxoSealDef`], errors with
+/// [`TxoSealError::SelfFallback`] if the fallback outpoint is equal to the
+/// primary one.
+impl<D: dbc::Proof> FromStr for TxoSeal<D> {
+    type Err = TxoSealParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let def = TxoSealDef::from_str(s)?;
+        Ok(Self::new(def.primary, def.secondary)?)
+    }
+}
+
 // Manual impl is needed since we need to avoid D: Copy bound
 impl<D: dbc::Proof> Copy for TxoSeal<D> {}
 impl<D: dbc::Proof> PartialOrd for TxoSeal<D> {
@@ -277,6 +579,44 @@ impl<D: dbc::Proof> Ord for TxoSeal<D> {
 }
 
 impl<D: dbc::Proof> TxoSeal<D> {
+    /// Constructs seal from the primary outpoint and the secondary seal data.
+    ///
+    /// # Errors
+    ///
+    /// Errors with [`TxoSealError::SelfFallback`] if the secondary data is a fallback outpoint
+    /// equal to the `primary` one.
+    pub fn new(primary: Outpoint, secondary: TxoSealExt) -> Result<Self, TxoSealError> {
+        match secondary {
+            TxoSealExt::Fallback(fallback) if fallback == primary => {
+                Err(TxoSealError::SelfFallback(primary))
+            }
+            _ => Ok(Self {
+                primary,
+                secondary,
+                _phantom: PhantomData,
+            }),
+        }
+    }
+
+    /// Constructs seal without a fallback, using the provided `noise` as the secondary seal data.
+    pub fn with_noise(primary: Outpoint, noise: Noise) -> Self {
+        Self {
+            primary,
+            secondary: TxoSealExt::Noise(noise),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Constructs seal with a `fallback` outpoint.
+    ///
+    /// # Errors
+    ///
+    /// Errors with [`TxoSealError::SelfFallback`] if the `fallback` outpoint is equal to the
+    /// `primary` one.
+    pub fn with_fallback(primary: Outpoint, fallback: Outpoint) -> Result<Self, TxoSealError> {
+        Self::new(primary, TxoSealExt::Fallback(fallback))
+    }
+
     /// `nonce` is a deterministic incremental number, preventing from creating the same seal if the
     /// same output is used.
     pub fn vout_no_fallback(vout: Vout, noise_engine: Sha256, nonce: u64) -> Self {
@@ -307,6 +647,19 @@ impl<D: dbc::Proof> TxoSeal<D> {
     }
 
     pub fn to_definition(&self) -> TxoSealDef { TxoSealDef::from(*self) }
+
+    /// Checks whether the transaction spends the seal primary outpoint or its fallback, if any.
+    ///
+    /// Unlike [`SingleUseSeal::is_included`], this doesn't check the commitment to a message, and
+    /// can be used for a first-pass scan of transactions which may close the seal.
+    pub fn is_closed_by(&self, tx: &Tx) -> bool {
+        let fallback = match self.secondary {
+            TxoSealExt::Fallback(fallback) => Some(fallback),
+            TxoSealExt::Noise(_) => None,
+        };
+        tx.inputs()
+            .any(|input| input.prev_output == self.primary || Some(input.prev_output) == fallback)
+    }
 }
 
 impl<D: dbc::Proof> SingleUseSeal for TxoSeal<D> {
@@ -331,11 +684,39 @@ impl<D: dbc::Proof> SingleUseSeal for TxoSeal<D> {
 // TODO: It's not just a transaction, it should be an SPV proof
 impl<D: dbc::Proof> PublishedWitness<TxoSeal<D>> for Tx {
     type PubId = Txid;
-    type Error = D::Error;
+    type Error = TxoSealVerifyError<D::Error>;
 
     fn pub_id(&self) -> Txid { self.txid() }
     fn verify_commitment(&self, proof: Proof<D>) -> Result<(), Self::Error> {
-        proof.dbc_proof.verify(&proof.mpc_commit, self)
+        let Err(source) = proof.dbc_proof.verify(&proof.mpc_commit, self) else {
+            return Ok(());
+        };
+        // Diagnose the failure to report the reason in a clear way
+        Err(match D::METHOD {
+            Method::OpretFirst => {
+                match self.find_output(|txout| txout.script_pubkey.is_op_return()) {
+                    None => TxoSealVerifyError::NoCommitment {
+                        method: D::METHOD,
+                        source,
+                    },
+                    Some((_, txout)) => TxoSealVerifyError::CommitmentMismatch {
+                        expected: ScriptPubkey::op_return(proof.mpc_commit.as_slice()),
+                        found: txout.script_pubkey.clone(),
+                        source,
+                    },
+                }
+            }
+            Method::TapretFirst if !self.outputs().any(|txout| txout.script_pubkey.is_p2tr()) => {
+                TxoSealVerifyError::NoCommitment {
+                    method: D::METHOD,
+                    source,
+                }
+            }
+            Method::TapretFirst => TxoSealVerifyError::InvalidProof {
+                method: D::METHOD,
+                source,
+            },
+        })
     }
 }
 
@@ -393,3 +774,324 @@ pub enum AnchorError {
     #[display("message {0} is not part of the anchor")]
     Mmb(mmb::Message),
 }
+
+/// Errors in constructing [`TxoSeal`].
+#[derive(Clone, PartialEq, Eq, Hash, Error, Debug, Display)]
+#[display(doc_comments)]
+pub enum TxoSealError {
+    /// seal fallback outpoint {0} is the same as the primary seal outpoint.
+    SelfFallback(Outpoint),
+}
+
+/// Errors verifying [`TxoSeal`] closing commitment in a witness transaction.
+///
+/// Each variant keeps the deterministic bitcoin commitment verification error
+/// as its [`Error::source`].
+#[derive(Clone, PartialEq, Eq, Debug, Display)]
+#[display(doc_comments)]
+pub enum TxoSealVerifyError<E: Error> {
+    /// transaction doesn't contain an output which may hold a {method}
+    /// commitment. Details: {source}
+    NoCommitment {
+        /// Commitment method of the seal.
+        method: Method,
+        /// Commitment verification error.
+        source: E,
+    },
+
+    /// transaction commitment output has script pubkey {found:x}, while the
+    /// expected commitment script pubkey is {expected:x}. Details: {source}
+    CommitmentMismatch {
+        /// Script pubkey which the commitment output should have.
+        expected: ScriptPubkey,
+        /// Script pubkey found in the commitment output.
+        found: ScriptPubkey,
+        /// Commitment verification error.
+        source: E,
+    },
+
+    /// transaction output doesn't match the {method} commitment proof.
+    /// Details: {source}
+    InvalidProof {
+        /// Commitment method of the seal.
+        method: Method,
+        /// Commitment verification error.
+        source: E,
+    },
+}
+
+impl<E: Error + 'static> Error for TxoSealVerifyError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TxoSealVerifyError::NoCommitment { source, .. }
+            | TxoSealVerifyError::CommitmentMismatch { source, .. }
+            | TxoSealVerifyError::InvalidProof { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Errors parsing string representation of txout seals.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum TxoSealParseError {
+    /// seal '{0}' lacks secondary seal data separated with '/'.
+    NoSecondary(String),
+
+    /// invalid seal outpoint. Details: {0}
+    #[from]
+    Outpoint(OutpointParseError),
+
+    /// invalid seal noise value. Details: {0}
+    #[from]
+    Noise(hex::Error),
+
+    /// invalid seal. Details: {0}
+    #[from]
+    Seal(TxoSealError),
+}
+
+#[cfg(test)]
+mod test {
+    use bc::{LockTime, SeqNo, TxIn, TxVer};
+    use commit_verify::CommitmentId;
+
+    use super::mpc::{MessageMap, MessageMapError, MessageSource, ProtocolId};
+    use super::*;
+
+    #[test]
+    fn mmb_commitment_engine() {
+        let mut tag = Sha256::default();
+        tag.input_raw(<mmb::Commitment as CommitmentId>::TAG.as_bytes());
+        assert_eq!(tag.finish(), mmb::COMMITMENT_TAG_HASH);
+
+        let mut engine = mmb::commitment_engine();
+        engine.input_raw(&[0xAA; 32]);
+        let mut expected = Sha256::from_tag(<mmb::Commitment as CommitmentId>::TAG);
+        expected.input_raw(&[0xAA; 32]);
+        assert_eq!(mmb::Commitment::from(engine), mmb::Commitment::from(expected));
+    }
+
+    #[test]
+    fn bundle_proof_construction() {
+        let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+        for vout in 0..2u32 {
+            tx.push_input(TxIn {
+                prev_output: Outpoint::new(Txid::coinbase(), vout),
+                sig_script: none!(),
+                sequence: SeqNo::FINAL,
+                witness: none!(),
+            })
+            .unwrap();
+        }
+        let msg1 = mmb::Message::from([1u8; 32]);
+        let msg2 = mmb::Message::from([2u8; 32]);
+
+        let proof = mmb::BundleProof::with_tx_messages(&tx, [(0, msg1), (1, msg2)]).unwrap();
+        assert!(proof.verify(Outpoint::new(Txid::coinbase(), 1), msg2, &tx));
+        assert_eq!(
+            mmb::BundleProof::with_tx_messages(&tx, [(0, msg1), (2, msg2)]).unwrap_err(),
+            mmb::MmbError::InputOutOfRange {
+                index: 2,
+                inputs: 2
+            }
+        );
+        assert_eq!(
+            mmb::BundleProof::with_tx_messages(&tx, [(1, msg1), (1, msg2)]).unwrap_err(),
+            mmb::MmbError::DuplicateInput(1)
+        );
+        assert_eq!(
+            mmb::BundleProof::with_tx_messages(&tx, [(0, msg1), (0, msg1)]).unwrap_err(),
+            mmb::MmbError::DuplicateInput(0)
+        );
+
+        let mut proof = mmb::BundleProof::new();
+        assert_eq!(proof.try_insert(0, msg1), Ok(None));
+        assert_eq!(proof.try_insert(0, msg2), Ok(Some(msg1)));
+        for index in 1..u16::MAX as u32 {
+            proof.try_insert(index, msg1).unwrap();
+        }
+        assert_eq!(proof.try_insert(u32::MAX, msg1), Err(mmb::MmbError::TooManyMessages));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn txo_seal_ext_serde() {
+        let outpoint = Outpoint::new(Txid::from([0xAB; 32]), 2);
+        let fallback = TxoSealExt::Fallback(outpoint);
+        let json = serde_json::to_value(fallback).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "type": "fallback", "outpoint": outpoint.to_string() })
+        );
+        assert_eq!(serde_json::from_value::<TxoSealExt>(json).unwrap(), fallback);
+
+        let noise = TxoSealExt::Noise(Noise::from(Bytes::from_byte_array([0x11; 40])));
+        let json = serde_json::to_value(noise).unwrap();
+        assert_eq!(json["type"], "noise");
+        assert_eq!(serde_json::from_value::<TxoSealExt>(json).unwrap(), noise);
+
+        // The type tag is required, and data of one variant can't be taken for another
+        assert!(serde_json::from_str::<TxoSealExt>(&format!("\"{outpoint}\"")).is_err());
+        let json = serde_json::json!({ "type": "noise", "outpoint": outpoint.to_string() });
+        assert!(serde_json::from_value::<TxoSealExt>(json).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "opret")]
+    fn txo_seal_str_roundtrip() {
+        type Seal = TxoSeal<dbc::opret::OpretProof>;
+        let primary = Outpoint::new(Txid::from([0xAB; 32]), 2);
+        let fallback = Outpoint::new(Txid::from([0xCD; 32]), 0);
+
+        let seal = Seal::with_fallback(primary, fallback).unwrap();
+        let s = seal.to_string();
+        assert_eq!(s, format!("{primary}/{fallback}"));
+        assert_eq!(Seal::from_str(&s).unwrap(), seal);
+        assert_eq!(TxoSealDef::from_str(&s).unwrap(), seal.to_definition());
+        assert_eq!(seal.to_definition().to_string(), s);
+
+        let seal = Seal::with_noise(primary, Noise::from(Bytes::from_byte_array([0x11; 40])));
+        let s = seal.to_string();
+        assert_eq!(s, format!("{primary}/~{}", "11".repeat(40)));
+        assert_eq!(Seal::from_str(&s).unwrap(), seal);
+        assert_eq!(TxoSealDef::from_str(&s).unwrap(), seal.to_definition());
+
+        assert_eq!(
+            Seal::from_str(&primary.to_string()),
+            Err(TxoSealParseError::NoSecondary(primary.to_string()))
+        );
+        assert!(matches!(
+            Seal::from_str(&format!("{primary}/~1122")),
+            Err(TxoSealParseError::Noise(_))
+        ));
+        assert!(matches!(
+            Seal::from_str(&format!("{primary}/{}", fallback.txid)),
+            Err(TxoSealParseError::Outpoint(_))
+        ));
+        assert_eq!(
+            Seal::from_str(&format!("{primary}/{primary}")),
+            Err(TxoSealParseError::Seal(TxoSealError::SelfFallback(primary)))
+        );
+        // Seal definitions are informational and are not checked
+        assert!(TxoSealDef::from_str(&format!("{primary}/{primary}")).is_ok());
+    }
+
+    #[test]
+    fn message_map_dedup() {
+        let p1 = ProtocolId::from([1u8; 32]);
+        let p2 = ProtocolId::from([2u8; 32]);
+        let msg1 = mpc::Message::from([0xAA; 32]);
+        let msg2 = mpc::Message::from([0xBB; 32]);
+
+        let mut map = MessageMap::new();
+        map.insert(p1, msg1).unwrap();
+        map.insert(p1, msg1).unwrap();
+        assert_eq!(map.insert(p1, msg2), Err(MessageMapError::DuplicateProtocol(p1)));
+        assert_eq!(map.get(&p1), Some(&MessageSource::Single(msg1)));
+
+        let mut other = MessageMap::new();
+        other.insert(p2, msg2).unwrap();
+        other.insert(p1, msg2).unwrap();
+        assert_eq!(map.merge(other), Err(MessageMapError::DuplicateProtocol(p1)));
+        assert_eq!(map.len(), 1);
+
+        let mut other = MessageMap::new();
+        other.insert(p1, msg1).unwrap();
+        other.insert(p2, msg2).unwrap();
+        map.merge(other).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&p2), Some(&MessageSource::Single(msg2)));
+    }
+
+    #[test]
+    #[cfg(feature = "opret")]
+    fn txo_seal_constructors() {
+        type Seal = TxoSeal<dbc::opret::OpretProof>;
+
+        let primary = Outpoint::new(Txid::coinbase(), 0);
+        let fallback = Outpoint::new(Txid::coinbase(), 1);
+        let noise = Noise::from(Bytes::from_byte_array([0xA5u8; 40]));
+
+        let seal = Seal::with_noise(primary, noise);
+        assert_eq!(seal.primary, primary);
+        assert_eq!(seal.secondary, TxoSealExt::Noise(noise));
+        assert_eq!(Seal::new(primary, TxoSealExt::Noise(noise)).unwrap(), seal);
+
+        let seal = Seal::with_fallback(primary, fallback).unwrap();
+        assert_eq!(seal.secondary, TxoSealExt::Fallback(fallback));
+        assert_eq!(
+            Seal::with_fallback(primary, primary).unwrap_err(),
+            TxoSealError::SelfFallback(primary)
+        );
+        assert_eq!(
+            Seal::new(primary, TxoSealExt::Fallback(primary)).unwrap_err(),
+            TxoSealError::SelfFallback(primary)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "opret")]
+    fn txo_seal_is_closed_by() {
+        type Seal = TxoSeal<dbc::opret::OpretProof>;
+
+        let primary = Outpoint::new(Txid::coinbase(), 0);
+        let fallback = Outpoint::new(Txid::coinbase(), 1);
+        let other = Outpoint::new(Txid::coinbase(), 2);
+        let spending = |outpoint: Outpoint| {
+            Tx::spending(outpoint, bc::TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64))
+        };
+
+        let seal = Seal::with_noise(primary, Noise::from(Bytes::from_byte_array([0u8; 40])));
+        assert!(seal.is_closed_by(&spending(primary)));
+        assert!(!seal.is_closed_by(&spending(fallback)));
+
+        let seal = Seal::with_fallback(primary, fallback).unwrap();
+        assert!(seal.is_closed_by(&spending(primary)));
+        assert!(seal.is_closed_by(&spending(fallback)));
+        assert!(!seal.is_closed_by(&spending(other)));
+        assert!(!seal.is_closed_by(&Tx::new(TxVer::V2, LockTime::ZERO)));
+    }
+
+    #[test]
+    #[cfg(feature = "opret")]
+    fn verify_commitment_errors() {
+        use bc::TxOut;
+        use commit_verify::EmbedVerifyError;
+        use dbc::opret::OpretError;
+
+        type Seal = TxoSeal<dbc::opret::OpretProof>;
+
+        let mpc_commit = mpc::Commitment::from([0xAB; 32]);
+        let proof = || Proof {
+            mpc_commit,
+            dbc_proof: dbc::opret::OpretProof::default(),
+        };
+        let tx_with = |script_pubkey: Option<ScriptPubkey>| {
+            let mut tx = Tx::new(TxVer::V2, LockTime::ZERO);
+            tx.push_output(TxOut::new(ScriptPubkey::p2wpkh([1u8; 20]), 1000u64)).unwrap();
+            if let Some(script_pubkey) = script_pubkey {
+                tx.push_output(TxOut::new(script_pubkey, 0u64)).unwrap();
+            }
+            tx
+        };
+        let verify = |tx: &Tx| <Tx as PublishedWitness<Seal>>::verify_commitment(tx, proof());
+
+        let expected = ScriptPubkey::op_return(mpc_commit.as_slice());
+        assert_eq!(verify(&tx_with(Some(expected.clone()))), Ok(()));
+        assert_eq!(
+            verify(&tx_with(None)),
+            Err(TxoSealVerifyError::NoCommitment {
+                method: Method::OpretFirst,
+                source: EmbedVerifyError::InvalidMessage(OpretError::NoOpretOutput)
+            })
+        );
+        let found = ScriptPubkey::op_return(&[0xCD; 32]);
+        let err = verify(&tx_with(Some(found.clone()))).unwrap_err();
+        assert_eq!(err, TxoSealVerifyError::CommitmentMismatch {
+            expected,
+            found,
+            source: EmbedVerifyError::CommitmentMismatch
+        });
+        assert!(err.source().is_some());
+    }
+}
@@ -0,0 +1,145 @@
+// Bitcoin protocol core library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use amplify::confinement::{Confined, U24};
+use amplify::hex::{FromHex, ToHex};
+use bp::{Tx, Xpub, HARDENED_INDEX_BOUNDARY};
+use dbc::Method;
+use seals::{mmb, Anchor, TxoSeal};
+use single_use_seals::SealWitness;
+use strict_encoding::StrictDeserialize;
+
+const USAGE: &str = "Usage:
+  bpcore xpub inspect <XPUB>
+  bpcore xpub derive <XPUB> <PATH>
+  bpcore seal verify --tx <HEX> --anchor <FILE> --seal <SEAL> --message <HEX> [--method <METHOD>]
+
+PATH is a sequence of non-hardened indexes separated by '/', like `0/1`.
+SEAL is a seal definition `<txid>:<vout>/<secondary>`, where the secondary part is either a
+fallback outpoint `<txid>:<vout>` or the seal noise in hex prefixed with `~`.
+METHOD is the anchor commitment method, either `tapret1st` (default) or `opret1st`.";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let res = match args.as_slice() {
+        ["xpub", "inspect", xpub] => xpub_inspect(xpub),
+        ["xpub", "derive", xpub, path] => xpub_derive(xpub, path),
+        ["seal", "verify", flags @ ..] => seal_verify(flags),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn xpub_inspect(xpub: &str) -> Result<(), Box<dyn Error>> {
+    let xpub = Xpub::from_str(xpub)?;
+    let network = if xpub.version == Xpub::VERSION_MAINNET { "mainnet" } else { "testnet" };
+    let child_number = if xpub.is_hardened() {
+        format!("{}h", xpub.child_number - HARDENED_INDEX_BOUNDARY)
+    } else {
+        xpub.child_number.to_string()
+    };
+    println!("Network:            {network}");
+    println!("Depth:              {}", xpub.depth);
+    println!("Parent fingerprint: {}", xpub.parent_fingerprint.to_hex());
+    println!("Child number:       {child_number}");
+    println!("Chain code:         {}", xpub.chain_code.to_hex());
+    println!("Public key:         {}", xpub.public_key);
+    println!("X-only public key:  {}", xpub.to_x_only_pk());
+    println!("Fingerprint:        {}", xpub.fingerprint().to_hex());
+    Ok(())
+}
+
+fn xpub_derive(xpub: &str, path: &str) -> Result<(), Box<dyn Error>> {
+    let xpub = Xpub::from_str(xpub)?;
+    let path = path
+        .split('/')
+        .filter(|index| !index.is_empty() && *index != "m")
+        .map(|index| {
+            if let Some(index) = index.strip_suffix(['h', '\'']) {
+                return Ok(u32::from_str(index)? | HARDENED_INDEX_BOUNDARY);
+            }
+            u32::from_str(index)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    println!("{}", xpub.derive_path(path)?);
+    Ok(())
+}
+
+fn seal_verify(flags: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut tx = None;
+    let mut anchor = None;
+    let mut seal = None;
+    let mut message = None;
+    let mut method = Method::TapretFirst;
+    let mut iter = flags.iter();
+    while let Some(flag) = iter.next() {
+        let value = *iter.next().ok_or_else(|| format!("missing value for `{flag}`"))?;
+        match *flag {
+            "--tx" => tx = Some(Tx::from_str(value)?),
+            "--anchor" => anchor = Some(std::fs::read(value)?),
+            "--seal" => seal = Some(value),
+            "--message" => {
+                message = Some(mmb::Message::from_byte_array(<[u8; 32]>::from_hex(value)?))
+            }
+            "--method" => method = Method::from_str(value)?,
+            _ => return Err(format!("unknown argument `{flag}`\n\n{USAGE}").into()),
+        }
+    }
+    let tx = tx.ok_or("missing `--tx` argument")?;
+    let anchor = anchor.ok_or("missing `--anchor` argument")?;
+    let seal = seal.ok_or("missing `--seal` argument")?;
+    let message = message.ok_or("missing `--message` argument")?;
+
+    match method {
+        Method::OpretFirst => verify_closing::<dbc::opret::OpretProof>(tx, anchor, seal, message)?,
+        Method::TapretFirst => {
+            verify_closing::<dbc::tapret::TapretProof>(tx, anchor, seal, message)?
+        }
+    }
+    println!("OK");
+    Ok(())
+}
+
+fn verify_closing<D: dbc::Proof + 'static>(
+    tx: Tx,
+    anchor: Vec<u8>,
+    seal: &str,
+    message: mmb::Message,
+) -> Result<(), Box<dyn Error>> {
+    let anchor = Anchor::<D>::from_strict_serialized::<U24>(Confined::try_from(anchor)?)?;
+    let seal = TxoSeal::<D>::from_str(seal)?;
+    SealWitness::new(tx, anchor).verify_seal_closing(seal, message)?;
+    Ok(())
+}
@@ -0,0 +1,364 @@
+// Bitcoin protocol core library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal support for BIP32 extended public keys, built on top of the
+//! consensus-level key types.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use base58::{FromBase58, FromBase58Error, ToBase58};
+use bc::secp256k1::{Scalar, SECP256K1};
+use bc::{CompressedPk, PubkeyHash, Sha256d, XOnlyPk};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+/// Index from which hardened child numbers start.
+pub const HARDENED_INDEX_BOUNDARY: u32 = 0x8000_0000;
+
+/// Errors parsing or using BIP32 extended public keys.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum Bip32Error {
+    /// invalid base58 character '{0}'.
+    Base58Char(char),
+
+    /// invalid base58 string length.
+    Base58Length,
+
+    /// invalid base58check checksum.
+    Checksum,
+
+    /// extended public key must be 78 bytes long, while {0} bytes were found.
+    DataLength(usize),
+
+    /// unknown extended public key version {0:#010x}.
+    UnknownVersion(u32),
+
+    /// master extended public key (having zero depth) must have zero parent
+    /// fingerprint and child number.
+    InvalidMaster,
+
+    /// extended key data contain invalid public key.
+    InvalidPubkey,
+
+    /// hardened derivation with index {0:#010x} is not possible for extended
+    /// public keys.
+    HardenedDerivation(u32),
+
+    /// derivation depth exceeds the maximum of 255 levels.
+    DepthOverflow,
+
+    /// derivation with index {0} produces an invalid key; the next index must
+    /// be used instead.
+    InvalidChild(u32),
+}
+
+/// BIP32 extended public key.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Xpub {
+    /// Version bytes, distinguishing mainnet (`xpub`) from testnet (`tpub`)
+    /// keys.
+    pub version: u32,
+    /// Derivation depth, with zero for the master key.
+    pub depth: u8,
+    /// Fingerprint of the parent key.
+    pub parent_fingerprint: [u8; 4],
+    /// Child number used in derivation of this key from its parent.
+    pub child_number: u32,
+    /// Chain code.
+    pub chain_code: [u8; 32],
+    /// Public key.
+    pub public_key: CompressedPk,
+}
+
+impl Xpub {
+    /// Version bytes of mainnet extended public keys (`xpub`).
+    pub const VERSION_MAINNET: u32 = 0x0488_B21E;
+    /// Version bytes of testnet extended public keys (`tpub`).
+    pub const VERSION_TESTNET: u32 = 0x0435_87CF;
+    /// Length of the binary serialization of the extended public key.
+    pub const LEN: usize = 78;
+
+    /// Parses extended public key from its binary serialization.
+    pub fn from_bytes(data: impl AsRef<[u8]>) -> Result<Self, Bip32Error> {
+        let data = data.as_ref();
+        if data.len() != Self::LEN {
+            return Err(Bip32Error::DataLength(data.len()));
+        }
+        let version = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        if version != Self::VERSION_MAINNET && version != Self::VERSION_TESTNET {
+            return Err(Bip32Error::UnknownVersion(version));
+        }
+        let depth = data[4];
+        let mut parent_fingerprint = [0u8; 4];
+        parent_fingerprint.copy_from_slice(&data[5..9]);
+        let child_number = u32::from_be_bytes([data[9], data[10], data[11], data[12]]);
+        if depth == 0 && (parent_fingerprint != [0u8; 4] || child_number != 0) {
+            return Err(Bip32Error::InvalidMaster);
+        }
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&data[13..45]);
+        let public_key =
+            CompressedPk::from_bytes(&data[45..]).map_err(|_| Bip32Error::InvalidPubkey)?;
+        Ok(Xpub {
+            version,
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            public_key,
+        })
+    }
+
+    /// Serializes extended public key into its binary representation.
+    pub fn to_bytes(&self) -> [u8; 78] {
+        let mut data = [0u8; 78];
+        data[..4].copy_from_slice(&self.version.to_be_bytes());
+        data[4] = self.depth;
+        data[5..9].copy_from_slice(&self.parent_fingerprint);
+        data[9..13].copy_from_slice(&self.child_number.to_be_bytes());
+        data[13..45].copy_from_slice(&self.chain_code);
+        data[45..].copy_from_slice(&self.public_key.to_byte_array());
+        data
+    }
+
+    /// Returns x-only representation of the public key.
+    pub fn to_x_only_pk(&self) -> XOnlyPk { XOnlyPk::from(self.public_key) }
+
+    /// Returns key identifier, which is a HASH160 of the public key.
+    pub fn identifier(&self) -> PubkeyHash { PubkeyHash::from(self.public_key) }
+
+    /// Returns key fingerprint, which is the first four bytes of the key
+    /// identifier.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        let mut fingerprint = [0u8; 4];
+        fingerprint.copy_from_slice(&self.identifier()[..4]);
+        fingerprint
+    }
+
+    /// Detects whether the key was produced by a hardened derivation.
+    pub fn is_hardened(&self) -> bool { self.child_number >= HARDENED_INDEX_BOUNDARY }
+
+    /// Derives non-hardened child extended public key with the given `index`
+    /// according to BIP32.
+    ///
+    /// # Errors
+    ///
+    /// - if the index is in the hardened range;
+    /// - if the key already has the maximum depth;
+    /// - in the (negligibly rare) case when the derivation produces an invalid key, as defined by
+    ///   BIP32.
+    pub fn derive_child(&self, index: u32) -> Result<Xpub, Bip32Error> {
+        if index >= HARDENED_INDEX_BOUNDARY {
+            return Err(Bip32Error::HardenedDerivation(index));
+        }
+        let depth = self.depth.checked_add(1).ok_or(Bip32Error::DepthOverflow)?;
+
+        let mut data = [0u8; 37];
+        data[..33].copy_from_slice(&self.public_key.to_byte_array());
+        data[33..].copy_from_slice(&index.to_be_bytes());
+        let mut engine =
+            Hmac::<Sha512>::new_from_slice(&self.chain_code).expect("HMAC accepts any key length");
+        engine.update(&data);
+        let hmac = engine.finalize().into_bytes();
+
+        let mut tweak = [0u8; 32];
+        tweak.copy_from_slice(&hmac[..32]);
+        let tweak = Scalar::from_be_bytes(tweak).map_err(|_| Bip32Error::InvalidChild(index))?;
+        let public_key = self
+            .public_key
+            .add_exp_tweak(SECP256K1, &tweak)
+            .map_err(|_| Bip32Error::InvalidChild(index))?;
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&hmac[32..]);
+
+        Ok(Xpub {
+            version: self.version,
+            depth,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code,
+            public_key: public_key.into(),
+        })
+    }
+
+    /// Derives extended public key following a sequence of non-hardened
+    /// indexes.
+    pub fn derive_path(&self, path: impl IntoIterator<Item = u32>) -> Result<Xpub, Bip32Error> {
+        path.into_iter().try_fold(*self, |xpub, index| xpub.derive_child(index))
+    }
+}
+
+impl FromStr for Xpub {
+    type Err = Bip32Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut data = s.from_base58().map_err(|err| match err {
+            FromBase58Error::InvalidBase58Character(c, _) => Bip32Error::Base58Char(c),
+            FromBase58Error::InvalidBase58Length => Bip32Error::Base58Length,
+        })?;
+        if data.len() < 4 {
+            return Err(Bip32Error::Checksum);
+        }
+        let checksum = data.split_off(data.len() - 4);
+        if Sha256d::digest(&data)[..4] != checksum[..] {
+            return Err(Bip32Error::Checksum);
+        }
+        Self::from_bytes(data)
+    }
+}
+
+impl Display for Xpub {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut data = self.to_bytes().to_vec();
+        data.extend_from_slice(&Sha256d::digest(&data)[..4]);
+        f.write_str(&data.to_base58())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::FromHex;
+
+    use super::*;
+
+    const H: u32 = HARDENED_INDEX_BOUNDARY;
+
+    // BIP32 test vector 1: m/0h/1/2h/2/1000000000
+    const VECTOR_1: [(u32, &str); 6] = [
+        (0, "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"),
+        (H, "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw"),
+        (1, "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ"),
+        (H + 2, "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5"),
+        (2, "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV"),
+        (1_000_000_000, "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy"),
+    ];
+
+    // BIP32 test vector 2: m/0/2147483647h/1/2147483646h/2
+    const VECTOR_2: [(u32, &str); 6] = [
+        (0, "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB"),
+        (0, "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH"),
+        (H + 2147483647, "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a"),
+        (1, "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon"),
+        (H + 2147483646, "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL"),
+        (2, "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt"),
+    ];
+
+    const MASTER: &str = VECTOR_1[0].1;
+    const MASTER_XPRV: &str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+
+    #[test]
+    fn test_vectors() {
+        for vector in [VECTOR_1, VECTOR_2] {
+            let master = Xpub::from_str(vector[0].1).unwrap();
+            assert_eq!(master.depth, 0);
+            assert_eq!(master.to_string(), vector[0].1);
+
+            let mut parent = master;
+            for (index, xpub) in &vector[1..] {
+                let child = Xpub::from_str(xpub).unwrap();
+                assert_eq!(child.to_string(), *xpub);
+                assert_eq!(Xpub::from_bytes(child.to_bytes()), Ok(child));
+                assert_eq!(child.version, Xpub::VERSION_MAINNET);
+                assert_eq!(child.depth, parent.depth + 1);
+                assert_eq!(child.child_number, *index);
+                assert_eq!(child.parent_fingerprint, parent.fingerprint());
+                assert_eq!(child.is_hardened(), *index >= H);
+                if child.is_hardened() {
+                    assert_eq!(
+                        parent.derive_child(*index),
+                        Err(Bip32Error::HardenedDerivation(*index))
+                    );
+                } else {
+                    assert_eq!(parent.derive_child(*index), Ok(child));
+                }
+                parent = child;
+            }
+        }
+    }
+
+    #[test]
+    fn parse_xpub() {
+        let master = Xpub::from_str(MASTER).unwrap();
+        assert_eq!(master.version, Xpub::VERSION_MAINNET);
+        assert_eq!(master.depth, 0);
+        assert_eq!(master.parent_fingerprint, [0u8; 4]);
+        assert_eq!(master.child_number, 0);
+        assert_eq!(
+            master.chain_code.to_vec(),
+            Vec::<u8>::from_hex("873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508")
+                .unwrap()
+        );
+        assert_eq!(
+            master.public_key.to_byte_array().to_vec(),
+            Vec::<u8>::from_hex(
+                "0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2"
+            )
+            .unwrap()
+        );
+        assert_eq!(master.to_string(), MASTER);
+
+        let child = Xpub::from_str(VECTOR_1[1].1).unwrap();
+        assert_eq!(child.depth, 1);
+        assert!(child.is_hardened());
+        assert_eq!(child.child_number, HARDENED_INDEX_BOUNDARY);
+        assert_eq!(child.parent_fingerprint, master.fingerprint());
+        assert_eq!(child.parent_fingerprint, [0x34, 0x42, 0x19, 0x3e]);
+        assert_eq!(child.to_string(), VECTOR_1[1].1);
+        assert_eq!(Xpub::from_bytes(child.to_bytes()), Ok(child));
+    }
+
+    #[test]
+    fn parse_xpub_errors() {
+        assert_eq!(Xpub::from_str(MASTER_XPRV), Err(Bip32Error::UnknownVersion(0x0488_ADE4)));
+        assert_eq!(Xpub::from_str(&MASTER.replace('M', "N")), Err(Bip32Error::Checksum));
+        assert_eq!(Xpub::from_str(&MASTER.replace('M', "0")), Err(Bip32Error::Base58Char('0')));
+        assert_eq!(Xpub::from_bytes([0u8; 77]), Err(Bip32Error::DataLength(77)));
+
+        let mut data = Xpub::from_str(MASTER).unwrap().to_bytes();
+        data[12] = 1;
+        assert_eq!(Xpub::from_bytes(data), Err(Bip32Error::InvalidMaster));
+        data[12] = 0;
+        data[45] = 0x04;
+        assert_eq!(Xpub::from_bytes(data), Err(Bip32Error::InvalidPubkey));
+    }
+
+    #[test]
+    fn derive_child() {
+        let parent = Xpub::from_str(VECTOR_1[1].1).unwrap();
+        let child = parent.derive_child(1).unwrap();
+        assert_eq!(child.to_string(), VECTOR_1[2].1);
+        assert_eq!(child.depth, 2);
+        assert_eq!(child.child_number, 1);
+        assert_eq!(child.parent_fingerprint, parent.fingerprint());
+        assert_eq!(parent.derive_path([1]), Ok(child));
+        assert_eq!(parent.derive_path([]), Ok(parent));
+        assert_eq!(
+            parent.derive_child(HARDENED_INDEX_BOUNDARY + 1),
+            Err(Bip32Error::HardenedDerivation(HARDENED_INDEX_BOUNDARY + 1))
+        );
+
+        let mut deepest = parent;
+        deepest.depth = u8::MAX;
+        assert_eq!(deepest.derive_child(0), Err(Bip32Error::DepthOverflow));
+    }
+}
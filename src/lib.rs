@@ -47,7 +47,6 @@ pub extern crate dbc;
 /// Re-export of `bp-seals` crate.
 pub extern crate seals;
 
-#[cfg(feature = "stl")]
 #[macro_use]
 extern crate amplify;
 #[macro_use]
@@ -58,6 +57,7 @@ extern crate serde;
 
 #[cfg(feature = "stl")]
 pub mod stl;
+mod bip32;
 mod bp;
 
 pub use ::bc::*;
@@ -66,4 +66,5 @@ pub use ::bc::*;
 pub mod bc {
     pub use bc::stl;
 }
+pub use bip32::{Bip32Error, Xpub, HARDENED_INDEX_BOUNDARY};
 pub use bp::Bp;